@@ -6,11 +6,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("🗜️  Tinify: Compressing Images Example");
     println!("==========================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Example 1: Compress from file
@@ -28,10 +25,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             println!("   ✅ Compressed image saved to compressed_output.png");
 
             // Get image data to buffer
-            let client2 = Tinify::new(
-                std::env::var("TINIFY_API_KEY")
-                    .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string()),
-            )?;
+            let client2 = Tinify::from_env()?;
             let source2 = client2.source_from_file("test_input.png").await?;
             let buffer = source2.to_buffer().await?;
             println!("   ✅ Image data loaded to buffer: {} bytes", buffer.len());
@@ -6,11 +6,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("📏 Tinify: Resizing Images Example");
     println!("====================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create a test image
@@ -21,11 +18,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     // Example 1: Scale method - maintains aspect ratio
     println!("\n🔄 Example 1: Scale method (maintains aspect ratio)");
-    let scale_options = ResizeOptions {
-        method: ResizeMethod::Scale,
-        width: Some(150),
-        height: None, // Let it maintain aspect ratio
-    };
+    // Scale takes a single dimension; use fit to bound both width and height
+    let scale_options = ResizeOptions::scale_width(150);
 
     match source.resize(scale_options).await {
         Ok(mut result) => {
@@ -1,16 +1,13 @@
 use std::error::Error;
-use tinify::{ConvertOptions, ImageFormat, Tinify};
+use tinify::{ConvertOptions, ImageFormat, Tinify, TinifyError};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("🔄 Tinify: Converting Images Example");
     println!("======================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create test images
@@ -94,10 +91,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 println!("   📄 Content type: {}", content_type);
             }
         }
-        Err(e) => {
-            println!("   ❌ AVIF conversion error: {}", e);
-            println!("   ℹ️  AVIF might not be supported in all regions/plans");
+        Err(TinifyError::FormatNotAvailable { .. }) => {
+            // AVIF isn't offered in all regions/plans, fall back to WebP
+            println!("   ℹ️  AVIF is not available, falling back to WebP");
+            let mut result = source4
+                .convert(ConvertOptions::new(ImageFormat::WebP))
+                .await?;
+            result.to_file("converted_to_avif_fallback.webp").await?;
+            println!("   ✅ Converted to WebP: converted_to_avif_fallback.webp");
         }
+        Err(e) => println!("   ❌ AVIF conversion error: {}", e),
     }
 
     // Example 5: Convert PNG to PNG (essentially recompress with optimization)
@@ -6,16 +6,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("🏷️  Tinify: Preserving Metadata Example");
     println!("=========================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create test image with metadata
     create_test_image_with_metadata("metadata_input.jpg").await?;
 
+    // Upload once; every example below reuses this upload instead of reading and
+    // compressing the file again
     let source = client.source_from_file("metadata_input.jpg").await?;
     println!("✅ Source image with metadata loaded");
 
@@ -43,8 +42,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         preserve: vec![PreserveMetadata::Creation],
     };
 
-    let source2 = client.source_from_file("metadata_input.jpg").await?;
-    match source2.preserve(creation_options).await {
+    match source.preserve(creation_options).await {
         Ok(mut result) => {
             result.to_file("preserved_creation.jpg").await?;
             println!("   ✅ Image with preserved creation date saved: preserved_creation.jpg");
@@ -62,8 +60,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         preserve: vec![PreserveMetadata::Location],
     };
 
-    let source3 = client.source_from_file("metadata_input.jpg").await?;
-    match source3.preserve(location_options).await {
+    match source.preserve(location_options).await {
         Ok(mut result) => {
             result.to_file("preserved_location.jpg").await?;
             println!("   ✅ Image with preserved location data saved: preserved_location.jpg");
@@ -85,8 +82,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         ],
     };
 
-    let source4 = client.source_from_file("metadata_input.jpg").await?;
-    match source4.preserve(multi_options).await {
+    match source.preserve(multi_options).await {
         Ok(mut result) => {
             result.to_file("preserved_all_metadata.jpg").await?;
             println!("   ✅ Image with all metadata preserved: preserved_all_metadata.jpg");
@@ -103,8 +99,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     // Example 5: Compare with non-preserved version
     println!("\n🔍 Example 5: Comparing preserved vs non-preserved");
-    let source5 = client.source_from_file("metadata_input.jpg").await?;
-    match source5.to_buffer().await {
+    match source.to_buffer().await {
         Ok(buffer) => {
             tokio::fs::write("no_metadata_preserved.jpg", buffer).await?;
             println!("   ✅ Image without metadata preservation: no_metadata_preserved.jpg");
@@ -130,31 +125,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     // Example 6: Demonstrate metadata preservation with different formats
     println!("\n🔄 Example 6: Metadata preservation with format conversion");
-    use tinify::{ConvertOptions, ImageFormat};
+    use tinify::{ConvertOptions, ImageFormat, TransformOptions};
 
     let convert_options = ConvertOptions {
         format: ImageFormat::Png,
         background: Some("#FFFFFF".to_string()),
     };
 
-    let source6 = client.source_from_file("metadata_input.jpg").await?;
-    match source6.convert(convert_options).await {
-        Ok(converted_result) => {
-            // Now preserve metadata on the converted image
-            let source7 = client.source_from_buffer(converted_result.into()).await?;
-            let preserve_options = PreserveOptions {
-                preserve: vec![PreserveMetadata::Copyright],
-            };
-
-            match source7.preserve(preserve_options).await {
-                Ok(mut final_result) => {
-                    final_result.to_file("converted_with_metadata.png").await?;
-                    println!("   ✅ Converted format with preserved metadata: converted_with_metadata.png");
-                }
-                Err(e) => println!("   ❌ Format conversion + metadata error: {}", e),
-            }
+    // Converting and preserving in one request counts as a single compression,
+    // instead of converting, re-uploading the result and preserving separately
+    let transform_options = TransformOptions::new()
+        .convert(convert_options)
+        .preserve(vec![PreserveMetadata::Copyright]);
+
+    match source.transform(transform_options).await {
+        Ok(mut final_result) => {
+            final_result.to_file("converted_with_metadata.png").await?;
+            println!("   ✅ Converted format with preserved metadata: converted_with_metadata.png");
         }
-        Err(e) => println!("   ❌ Format conversion error: {}", e),
+        Err(e) => println!("   ❌ Format conversion + metadata error: {}", e),
     }
 
     println!("\n🎉 Metadata preservation examples completed!");
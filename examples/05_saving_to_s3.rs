@@ -1,17 +1,14 @@
 use serde_json::json;
 use std::error::Error;
-use tinify::{S3Options, StoreOptions, Tinify};
+use tinify::{S3Acl, S3Options, StoreOptions, Tinify};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("📦 Tinify: Saving to Amazon S3 Example");
     println!("========================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create test image
@@ -32,11 +29,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
         path: "my-bucket/images/compressed-image.png".to_string(),
         headers: None,
         acl: None,
+        endpoint: None,
     };
 
     match source.store(StoreOptions::S3(s3_options)).await {
         Ok(result) => {
             println!("   ✅ Image stored to S3 successfully!");
+            if let Some(location) = result.location() {
+                println!("   📍 Location: {}", location);
+            }
             if let Some(compression_count) = result.compression_count() {
                 println!("   📈 Compression count: {}", compression_count);
             }
@@ -58,7 +59,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         region: "us-west-2".to_string(),
         path: "my-public-bucket/images/public-image.png".to_string(),
         headers: None,
-        acl: Some("public-read".to_string()),
+        acl: Some(S3Acl::PublicRead),
+        endpoint: None,
     };
 
     let source2 = client.source_from_file("s3_input.png").await?;
@@ -88,8 +90,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .unwrap_or_else(|_| "DEMO_SECRET_ACCESS_KEY".to_string()),
         region: "eu-west-1".to_string(),
         path: "my-cdn-bucket/assets/cached-image.png".to_string(),
-        headers: Some(custom_headers),
-        acl: Some("public-read".to_string()),
+        headers: Some(custom_headers.into()),
+        acl: Some(S3Acl::PublicRead),
+        endpoint: None,
     };
 
     let source3 = client.source_from_file("s3_input.png").await?;
@@ -123,6 +126,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             path: format!("{}/images/region-test.png", bucket),
             headers: None,
             acl: None,
+            endpoint: None,
         };
 
         let source_region = client.source_from_file("s3_input.png").await?;
@@ -158,6 +162,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             path: path.to_string(),
             headers: None,
             acl: None,
+            endpoint: None,
         };
 
         let source_path = client.source_from_file("s3_input.png").await?;
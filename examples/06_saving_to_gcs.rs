@@ -7,11 +7,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("☁️  Tinify: Saving to Google Cloud Storage Example");
     println!("==================================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create test image
@@ -57,7 +54,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         gcp_access_token: std::env::var("GCP_ACCESS_TOKEN")
             .unwrap_or_else(|_| "DEMO_GCP_ACCESS_TOKEN".to_string()),
         path: "my-gcs-bucket/assets/header-demo.png".to_string(),
-        headers: Some(custom_headers),
+        headers: Some(custom_headers.into()),
     };
 
     let source2 = client.source_from_file("gcs_input.png").await?;
@@ -117,7 +114,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         gcp_access_token: std::env::var("GCP_ACCESS_TOKEN")
             .unwrap_or_else(|_| "DEMO_GCP_ACCESS_TOKEN".to_string()),
         path: "my-metadata-bucket/processed/metadata-demo.png".to_string(),
-        headers: Some(metadata_headers),
+        headers: Some(metadata_headers.into()),
     };
 
     let source4 = client.source_from_file("gcs_input.png").await?;
@@ -149,7 +146,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             service: "gcs".to_string(),
             gcp_access_token: "DEMO_GCP_ACCESS_TOKEN".to_string(),
             path: path.to_string(),
-            headers: Some(batch_headers),
+            headers: Some(batch_headers.into()),
         };
 
         let source_batch = client.source_from_file("gcs_input.png").await?;
@@ -225,14 +222,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     "X-Goog-Meta-Converted-Format": ext.to_uppercase()
                 });
 
-                let source_bytes = converted_result.into();
+                let source_bytes = converted_result.bytes().await?;
                 let source_converted = client.source_from_buffer(source_bytes).await?;
 
                 let gcs_format_options = GCSOptions {
                     service: "gcs".to_string(),
                     gcp_access_token: "DEMO_GCP_ACCESS_TOKEN".to_string(),
                     path: format!("format-demo-bucket/converted/image.{}", ext),
-                    headers: Some(format_headers),
+                    headers: Some(format_headers.into()),
                 };
 
                 match source_converted
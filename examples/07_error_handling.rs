@@ -6,11 +6,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("⚠️  Tinify: Error Handling Example");
     println!("===================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Example 1: Invalid API key error
@@ -132,10 +129,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
             println!("   ✅ Correctly caught invalid dimensions error:");
             println!("      Error: {}", e);
             match e {
-                TinifyError::InvalidDimensions { width, height } => {
+                TinifyError::InvalidDimensions { width, height, max } => {
                     println!("      Type: InvalidDimensions");
                     println!("      Width: {:?}", width);
                     println!("      Height: {:?}", height);
+                    println!("      Max: {}", max);
                 }
                 _ => println!("      Type: Other"),
             }
@@ -152,8 +150,26 @@ async fn main() -> Result<(), Box<dyn Error>> {
             println!("   ✅ Network error caught:");
             println!("      Error: {}", e);
             match e {
-                TinifyError::ConnectionError(_) => {
-                    println!("      Type: ConnectionError");
+                // Retryable failures are wrapped once every attempt has failed
+                TinifyError::RetriesExhausted {
+                    attempts, source, ..
+                } => {
+                    println!("      Type: RetriesExhausted after {} attempts", attempts);
+                    match *source {
+                        TinifyError::ConnectionError(_) => {
+                            println!("      Last attempt: ConnectionError");
+                        }
+                        TinifyError::ServerError { status, .. } => {
+                            println!("      Last attempt: ServerError (Status: {:?})", status);
+                        }
+                        TinifyError::RateLimitExceeded { retry_after, .. } => {
+                            println!(
+                                "      Last attempt: RateLimitExceeded (retry after {}s)",
+                                retry_after
+                            );
+                        }
+                        other => println!("      Last attempt: {}", other),
+                    }
                 }
                 TinifyError::AccountError { status, .. } => {
                     println!("      Type: AccountError (Status: {:?})", status);
@@ -161,9 +177,6 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 TinifyError::ClientError { status, .. } => {
                     println!("      Type: ClientError (Status: {:?})", status);
                 }
-                TinifyError::ServerError { status, .. } => {
-                    println!("      Type: ServerError (Status: {:?})", status);
-                }
                 _ => println!("      Type: Other - {}", e),
             }
         }
@@ -229,18 +242,34 @@ async fn main() -> Result<(), Box<dyn Error>> {
             match e {
                 TinifyError::InvalidApiKey => println!("         🔑 Authentication issue"),
                 TinifyError::FileNotFound { .. } => println!("         📁 File system issue"),
-                TinifyError::UnsupportedFormat { .. } => println!("         🖼️  Format issue"),
-                TinifyError::FileTooLarge { .. } => println!("         📊 Size issue"),
+                TinifyError::UnsupportedFormat { .. } | TinifyError::FormatNotAvailable { .. } => {
+                    println!("         🖼️  Format issue")
+                }
+                TinifyError::FileTooLarge { .. } | TinifyError::EmptyInput => {
+                    println!("         📊 Size issue")
+                }
                 TinifyError::InvalidDimensions { .. } => println!("         📏 Dimension issue"),
+                TinifyError::InvalidBackground { .. } => println!("         🎨 Background issue"),
+                TinifyError::InvalidOptions { .. } => println!("         ⚙️  Options issue"),
                 TinifyError::ConnectionError(_) => println!("         🌐 Connection issue"),
                 TinifyError::AccountError { .. } => println!("         💳 Account issue"),
                 TinifyError::ClientError { .. } => println!("         📡 Client issue"),
                 TinifyError::ServerError { .. } => println!("         🖥️  Server issue"),
-                TinifyError::QuotaExceeded => println!("         💳 Quota issue"),
+                TinifyError::QuotaExceeded { .. } => println!("         💳 Quota issue"),
                 TinifyError::RateLimitExceeded { .. } => println!("         ⏱️  Rate limit issue"),
-                TinifyError::UrlParseError(_) => println!("         🔗 URL issue"),
+                TinifyError::UrlParseError(_) | TinifyError::InvalidUrl { .. } => {
+                    println!("         🔗 URL issue")
+                }
                 TinifyError::JsonError(_) => println!("         📄 JSON issue"),
+                TinifyError::Base64Error(_) | TinifyError::InvalidDataUri { .. } => {
+                    println!("         🧬 Encoding issue")
+                }
                 TinifyError::IoError(_) => println!("         💾 I/O issue"),
+                TinifyError::ChecksumMismatch { .. } => println!("         🔐 Integrity issue"),
+                TinifyError::Timeout { .. } => println!("         ⏱️  Timeout issue"),
+                TinifyError::RetriesExhausted { attempts, .. } => {
+                    println!("         🔁 Still failing after {} attempts", attempts)
+                }
                 TinifyError::UnknownError { .. } => println!("         ❓ Unknown issue"),
                 TinifyError::ClientNotInitialized => println!("         🚫 Client not initialized"),
             }
@@ -6,11 +6,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("📈 Tinify: Compression Count Example");
     println!("======================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Example 1: Basic compression count tracking
@@ -93,14 +90,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         }
                     }
                     "resize" => {
-                        match source
-                            .resize(tinify::ResizeOptions {
-                                method: tinify::ResizeMethod::Scale,
-                                width: Some(150),
-                                height: None,
-                            })
-                            .await
-                        {
+                        match source.resize(tinify::ResizeOptions::scale_width(150)).await {
                             Ok(mut result) => {
                                 result.to_file(&format!("{}_output.png", op_type)).await?;
                                 println!("      ✅ {} completed", description);
@@ -1,16 +1,13 @@
 use std::error::Error;
-use tinify::{S3Options, StoreOptions, Tinify};
+use tinify::{S3Acl, S3Options, StoreOptions, Tinify};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     println!("💾 Tinify: S3 Compatible Storage Example");
     println!("==========================================");
 
-    // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    // Initialize client from the TINIFY_API_KEY environment variable
+    let client = Tinify::from_env()?;
     println!("✅ Client initialized");
 
     // Create test image
@@ -30,12 +27,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
         region: "nyc3".to_string(), // DigitalOcean region
         path: "my-space/images/compressed-image.png".to_string(),
         headers: None,
-        acl: Some("public-read".to_string()),
+        acl: Some(S3Acl::PublicRead),
+        endpoint: None,
     };
 
     match source.store(StoreOptions::S3(do_spaces_options)).await {
         Ok(result) => {
             println!("   ✅ Image stored to DigitalOcean Spaces!");
+            if let Some(location) = result.location() {
+                println!("   📍 Location: {}", location);
+            }
             if let Some(count) = result.compression_count() {
                 println!("   📈 Compression count: {}", count);
             }
@@ -58,6 +59,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         path: "my-bucket/compressed/image.png".to_string(),
         headers: None,
         acl: None, // Backblaze handles ACL differently
+        endpoint: None,
     };
 
     let source2 = client.source_from_file("s3_compatible_input.png").await?;
@@ -82,7 +84,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         region: "us-east-1".to_string(), // Wasabi region
         path: "my-bucket/optimized/image.png".to_string(),
         headers: None,
-        acl: Some("public-read".to_string()),
+        acl: Some(S3Acl::PublicRead),
+        endpoint: None,
     };
 
     let source3 = client.source_from_file("s3_compatible_input.png").await?;
@@ -108,6 +111,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         path: "my-storage/images/compressed.png".to_string(),
         headers: None,
         acl: None,
+        endpoint: None,
     };
 
     let source4 = client.source_from_file("s3_compatible_input.png").await?;
@@ -133,6 +137,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         path: "my-bucket/uploads/processed.png".to_string(),
         headers: None,
         acl: None,
+        endpoint: None,
     };
 
     let source5 = client.source_from_file("s3_compatible_input.png").await?;
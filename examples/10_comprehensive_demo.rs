@@ -200,7 +200,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("📋 DEMO 5: Cloud Storage Integration");
     println!("───────────────────────────────────");
 
-    use tinify::{GCSOptions, S3Options, StoreOptions};
+    use tinify::{GCSOptions, S3Acl, S3Options, StoreOptions};
 
     // S3 demonstration
     let s3_options = S3Options {
@@ -210,7 +210,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         region: "us-east-1".to_string(),
         path: "demo-bucket/compressed-image.png".to_string(),
         headers: None,
-        acl: Some("public-read".to_string()),
+        acl: Some(S3Acl::PublicRead),
+        endpoint: None,
     };
 
     match client.source_from_file("demo_input.png").await {
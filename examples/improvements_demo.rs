@@ -18,6 +18,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         base_delay: Duration::from_millis(200),
         max_delay: Duration::from_secs(30),
         backoff_factor: 2.0,
+        max_total_duration: Some(Duration::from_secs(120)),
     };
 
     let rate_limit = RateLimit {
@@ -18,10 +18,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!();
 
     // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    let client = Tinify::from_env()?;
     println!("✅ Tinify client initialized");
 
     // Create test image
@@ -64,11 +61,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 service: "gcs".to_string(),
                 gcp_access_token: token,
                 path: "test-bucket-gcs/compressed-with-real-token.png".to_string(),
-                headers: Some(json!({
-                    "Cache-Control": "public, max-age=3600",
-                    "X-Goog-Meta-Source": "tinify-test",
-                    "X-Goog-Meta-Timestamp": chrono::Utc::now().to_rfc3339(),
-                })),
+                headers: Some(
+                    json!({
+                        "Cache-Control": "public, max-age=3600",
+                        "X-Goog-Meta-Source": "tinify-test",
+                        "X-Goog-Meta-Timestamp": chrono::Utc::now().to_rfc3339(),
+                    })
+                    .into(),
+                ),
             };
 
             let source2 = client.source_from_file("gcs_test_input.png").await?;
@@ -126,10 +126,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 service: "gcs".to_string(),
                 gcp_access_token: "your-token".to_string(),
                 path: "my-bucket/cached/image.png".to_string(),
-                headers: Some(json!({
-                    "Cache-Control": "public, max-age=31536000",
-                    "Content-Type": "image/png"
-                })),
+                headers: Some(
+                    json!({
+                        "Cache-Control": "public, max-age=31536000",
+                        "Content-Type": "image/png"
+                    })
+                    .into(),
+                ),
             },
         ),
         (
@@ -138,11 +141,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 service: "gcs".to_string(),
                 gcp_access_token: "your-token".to_string(),
                 path: "my-bucket/metadata/image.png".to_string(),
-                headers: Some(json!({
-                    "X-Goog-Meta-Project": "my-project",
-                    "X-Goog-Meta-Environment": "production",
-                    "X-Goog-Meta-Compressed-By": "tinify"
-                })),
+                headers: Some(
+                    json!({
+                        "X-Goog-Meta-Project": "my-project",
+                        "X-Goog-Meta-Environment": "production",
+                        "X-Goog-Meta-Compressed-By": "tinify"
+                    })
+                    .into(),
+                ),
             },
         ),
     ];
@@ -199,18 +205,21 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 println!("      ✅ Conversion successful");
 
                 // Simulate GCS upload (will fail with demo token but shows structure)
-                let converted_bytes = converted_result.into();
+                let converted_bytes = converted_result.bytes().await?;
                 let source_converted = client.source_from_buffer(converted_bytes).await?;
 
                 let gcs_format_options = GCSOptions {
                     service: "gcs".to_string(),
                     gcp_access_token: "demo-token".to_string(),
                     path: format!("test-bucket-gcs/converted.{}", ext),
-                    headers: Some(json!({
-                        "Content-Type": content_type,
-                        "X-Goog-Meta-Original-Format": "PNG",
-                        "X-Goog-Meta-Conversion": format!("PNG-to-{}", ext.to_uppercase())
-                    })),
+                    headers: Some(
+                        json!({
+                            "Content-Type": content_type,
+                            "X-Goog-Meta-Original-Format": "PNG",
+                            "X-Goog-Meta-Conversion": format!("PNG-to-{}", ext.to_uppercase())
+                        })
+                        .into(),
+                    ),
                 };
 
                 match source_converted
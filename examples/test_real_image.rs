@@ -1,5 +1,5 @@
 use std::error::Error;
-use tinify::{S3Options, StoreOptions, Tinify};
+use tinify::{S3Acl, S3Options, StoreOptions, Tinify};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -57,7 +57,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 region: "us-east-1".to_string(),
                 path: "test-bucket/real-image.png".to_string(),
                 headers: None,
-                acl: Some("public-read".to_string()),
+                acl: Some(S3Acl::PublicRead),
+                endpoint: None,
             };
 
             match source.store(StoreOptions::S3(s3_options)).await {
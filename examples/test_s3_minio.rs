@@ -21,10 +21,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!();
 
     // Initialize client with API key
-    let api_key = std::env::var("TINIFY_API_KEY")
-        .unwrap_or_else(|_| "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq".to_string());
-
-    let client = Tinify::new(api_key)?;
+    let client = Tinify::from_env()?;
     println!("✅ Tinify client initialized");
 
     // Create test image
@@ -46,6 +43,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         path: "test-bucket/compressed-image-basic.png".to_string(),
         headers: None,
         acl: None,
+        endpoint: None,
     };
 
     match source.store(StoreOptions::S3(minio_options)).await {
@@ -82,12 +80,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .unwrap_or_else(|_| "minioadmin".to_string()),
         region: "us-east-1".to_string(),
         path: "test-bucket/compressed-with-headers.png".to_string(),
-        headers: Some(custom_headers),
+        headers: Some(custom_headers.into()),
         acl: None,
+        endpoint: None,
     };
 
-    let source2 = client.source_from_file("minio_test_input.png").await?;
-    match source2.store(StoreOptions::S3(minio_headers_options)).await {
+    match source.store(StoreOptions::S3(minio_headers_options)).await {
         Ok(_) => {
             println!("   ✅ Image with custom headers stored to MinIO!");
             println!(
@@ -116,12 +114,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
             background: Some("#FFFFFF".to_string()),
         };
 
-        let source_convert = client.source_from_file("minio_test_input.png").await?;
-        match source_convert.convert(convert_options).await {
+        match source.convert(convert_options).await {
             Ok(converted_result) => {
-                // Convert to bytes for buffer upload
-                let converted_bytes = converted_result.into();
-                let source_converted = client.source_from_buffer(converted_bytes).await?;
+                // Reuse the upload: the store is sent together with the conversion,
+                // without downloading and re-uploading the converted bytes
+                let source_converted = converted_result
+                    .to_source()
+                    .expect("result of a source operation");
 
                 let format_headers = json!({
                     "Content-Type": content_type,
@@ -137,8 +136,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         .unwrap_or_else(|_| "minioadmin".to_string()),
                     region: "us-east-1".to_string(),
                     path: format!("test-bucket/converted-image.{}", ext),
-                    headers: Some(format_headers),
+                    headers: Some(format_headers.into()),
                     acl: None,
+                    endpoint: None,
                 };
 
                 match source_converted
@@ -171,11 +171,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         height: Some(200),
     };
 
-    let source_resize = client.source_from_file("minio_test_input.png").await?;
-    match source_resize.resize(resize_options).await {
+    match source.resize(resize_options).await {
         Ok(resized_result) => {
-            let resized_bytes = resized_result.into();
-            let source_resized = client.source_from_buffer(resized_bytes).await?;
+            let source_resized = resized_result
+                .to_source()
+                .expect("result of a source operation");
 
             let resize_options_s3 = S3Options {
                 service: "s3".to_string(),
@@ -185,8 +185,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     .unwrap_or_else(|_| "minioadmin".to_string()),
                 region: "us-east-1".to_string(),
                 path: "test-bucket/resized-image.png".to_string(),
-                headers: Some(json!({"X-Resize-Method": "fit", "X-Dimensions": "200x200"})),
+                headers: Some(json!({"X-Resize-Method": "fit", "X-Dimensions": "200x200"}).into()),
                 acl: None,
+                endpoint: None,
             };
 
             match source_resized
@@ -0,0 +1,665 @@
+use crate::error::{ErrorSnapshot, Result, TinifyError};
+use crate::{Tinify, SUPPORTED_FORMATS};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, instrument, warn};
+
+/// Number of files compressed concurrently by the batch operations
+const BATCH_CONCURRENCY: usize = 4;
+
+/// A file that was compressed successfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchSuccess {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// Size of the input file in bytes
+    pub input_size: u64,
+    /// Size of the compressed output in bytes
+    pub output_size: u64,
+}
+
+/// A file that could not be compressed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchFailure {
+    pub input: PathBuf,
+    pub error: ErrorSnapshot,
+}
+
+/// One file finished by [`Tinify::compress_dir_stream`]
+#[derive(Debug)]
+pub struct BatchItem {
+    pub input: PathBuf,
+    pub result: Result<BatchSuccess>,
+    /// Whether the upload used a compression, even if saving the result failed;
+    /// summing these gives [`BatchReport::compressions`]
+    pub compressed: bool,
+}
+
+/// Summary of a batch run by [`Tinify::compress_files`] or [`Tinify::compress_dir`]
+///
+/// Entries are listed in input order. The `Display` implementation renders a short
+/// summary followed by one line per failure, suitable for CLI output.
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub succeeded: Vec<BatchSuccess>,
+    pub failed: Vec<BatchFailure>,
+    /// Files that were not finished because the batch was cancelled
+    pub cancelled: Vec<PathBuf>,
+    /// Compressions consumed, including uploads whose result could not be saved
+    pub compressions: u32,
+}
+
+impl BatchReport {
+    /// Number of files processed, successfully or not, excluding cancelled files
+    pub fn processed(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    pub fn was_cancelled(&self) -> bool {
+        !self.cancelled.is_empty()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.failed.is_empty()
+    }
+
+    /// Total bytes saved across successfully compressed files
+    pub fn bytes_saved(&self) -> u64 {
+        self.succeeded
+            .iter()
+            .map(|item| item.input_size.saturating_sub(item.output_size))
+            .sum()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Processed {} files: {} succeeded, {} failed, {} bytes saved, {} compressions used",
+            self.processed(),
+            self.succeeded.len(),
+            self.failed.len(),
+            self.bytes_saved(),
+            self.compressions
+        )?;
+        if self.was_cancelled() {
+            write!(f, ", {} cancelled", self.cancelled.len())?;
+        }
+        for failure in &self.failed {
+            write!(
+                f,
+                "\n  {}: {}",
+                failure.input.display(),
+                failure.error.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one file: the saved sizes or the error, `None` if cancelled, and whether
+/// the upload counted
+struct ItemOutcome {
+    input: PathBuf,
+    result: Option<Result<BatchSuccess>>,
+    compressed: bool,
+}
+
+impl Tinify {
+    /// Compress `files` into `output_dir`, keeping their file names
+    ///
+    /// Files are processed a few at a time, subject to the client's rate limit. A failing
+    /// file doesn't stop the batch; its error is recorded in the report instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if `output_dir` cannot be created.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::from_env()?;
+    /// let report = client
+    ///     .compress_files(["a.png", "b.jpg"], "compressed")
+    ///     .await?;
+    /// println!("{}", report);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn compress_files<I, P>(
+        &self,
+        files: I,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<BatchReport>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.compress_files_cancellable(files, output_dir, &CancellationToken::new())
+            .await
+    }
+
+    /// Like [`Tinify::compress_files`], but stops when `cancel` is cancelled
+    ///
+    /// In-flight requests are aborted and files that haven't finished are listed in
+    /// [`BatchReport::cancelled`]. Files already saved stay in the report as usual.
+    /// Dropping the returned future also aborts all in-flight requests.
+    ///
+    /// Each request retries according to the client's [`RetryConfig`], so a batch of
+    /// failing files can make many attempts. Run the batch on a handle with a
+    /// [`RetryBudget`] to share one retry allowance between all of its files.
+    ///
+    /// [`RetryConfig`]: crate::RetryConfig
+    /// [`RetryBudget`]: crate::RetryBudget
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use std::time::Duration;
+    /// use tinify::{CancellationToken, RequestOptions, RetryBudget, Tinify};
+    ///
+    /// // At most 10 retries across the whole batch
+    /// let client = Tinify::from_env()?
+    ///     .with_request_options(RequestOptions::new().retry_budget(RetryBudget::new(10)));
+    /// let cancel = CancellationToken::new();
+    ///
+    /// let deadline = cancel.clone();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_secs(60)).await;
+    ///     deadline.cancel();
+    /// });
+    ///
+    /// let report = client
+    ///     .compress_files_cancellable(["a.png", "b.jpg"], "compressed", &cancel)
+    ///     .await?;
+    /// println!("{}", report);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, files, output_dir, cancel), fields(output_dir = %output_dir.as_ref().display()))]
+    pub async fn compress_files_cancellable<I, P>(
+        &self,
+        files: I,
+        output_dir: impl AsRef<Path>,
+        cancel: &CancellationToken,
+    ) -> Result<BatchReport>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let output_dir = output_dir.as_ref();
+        tokio::fs::create_dir_all(output_dir).await?;
+
+        let inputs: Vec<PathBuf> = files
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect();
+        info!("Compressing {} files", inputs.len());
+
+        let outcomes: Vec<ItemOutcome> = stream::iter(inputs)
+            .map(|input| self.compress_one(input, output_dir, cancel))
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut report = BatchReport::default();
+        for outcome in outcomes {
+            if outcome.compressed {
+                report.compressions += 1;
+            }
+            match outcome.result {
+                Some(Ok(success)) => report.succeeded.push(success),
+                Some(Err(err)) => {
+                    warn!("Failed to compress {}: {}", outcome.input.display(), err);
+                    report.failed.push(BatchFailure {
+                        input: outcome.input,
+                        error: err.to_snapshot(),
+                    });
+                }
+                None => report.cancelled.push(outcome.input),
+            }
+        }
+
+        info!("{}", report);
+        Ok(report)
+    }
+
+    /// Compress every supported image directly inside `input_dir` into `output_dir`
+    ///
+    /// Subdirectories and files without a supported image extension are skipped.
+    /// Files are processed in path order. See [`Tinify::compress_files`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `input_dir` cannot be read or `output_dir` cannot be created.
+    pub async fn compress_dir(
+        &self,
+        input_dir: impl AsRef<Path>,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<BatchReport> {
+        self.compress_dir_cancellable(input_dir, output_dir, &CancellationToken::new())
+            .await
+    }
+
+    /// Like [`Tinify::compress_dir`], but stops when `cancel` is cancelled
+    ///
+    /// See [`Tinify::compress_files_cancellable`].
+    pub async fn compress_dir_cancellable(
+        &self,
+        input_dir: impl AsRef<Path>,
+        output_dir: impl AsRef<Path>,
+        cancel: &CancellationToken,
+    ) -> Result<BatchReport> {
+        let files = Self::list_images(input_dir.as_ref()).await?;
+        self.compress_files_cancellable(files, output_dir, cancel)
+            .await
+    }
+
+    /// Like [`Tinify::compress_dir`], but yields each file as soon as it's done
+    ///
+    /// Items arrive in completion order rather than path order, so progress can be
+    /// shown incrementally without holding every result in memory. The same
+    /// concurrency and rate limits apply. Dropping the stream aborts in-flight
+    /// requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `input_dir` cannot be read or `output_dir` cannot be
+    /// created. Per-file errors are yielded by the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use futures_util::StreamExt;
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::from_env()?;
+    /// let mut items = std::pin::pin!(client.compress_dir_stream("images", "compressed").await?);
+    /// let mut compressions = 0;
+    /// while let Some(item) = items.next().await {
+    ///     compressions += u32::from(item.compressed);
+    ///     match item.result {
+    ///         Ok(success) => println!("{}: {} bytes", item.input.display(), success.output_size),
+    ///         Err(err) => eprintln!("{}: {}", item.input.display(), err),
+    ///     }
+    /// }
+    /// println!("{} compressions used", compressions);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn compress_dir_stream(
+        &self,
+        input_dir: impl AsRef<Path>,
+        output_dir: impl AsRef<Path>,
+    ) -> Result<impl Stream<Item = BatchItem> + '_> {
+        let files = Self::list_images(input_dir.as_ref()).await?;
+        let output_dir = output_dir.as_ref().to_path_buf();
+        tokio::fs::create_dir_all(&output_dir).await?;
+        info!("Streaming compression of {} files", files.len());
+
+        Ok(stream::iter(files)
+            .map(move |input| {
+                let output_dir = output_dir.clone();
+                async move {
+                    let mut compressed = false;
+                    let result = self
+                        .compress_to_dir(&input, &output_dir, &mut compressed)
+                        .await;
+                    BatchItem {
+                        input,
+                        result,
+                        compressed,
+                    }
+                }
+            })
+            .buffer_unordered(BATCH_CONCURRENCY))
+    }
+
+    /// Supported images directly inside `input_dir`, in path order
+    async fn list_images(input_dir: &Path) -> Result<Vec<PathBuf>> {
+        if !input_dir.is_dir() {
+            return Err(TinifyError::FileNotFound {
+                path: input_dir.to_path_buf(),
+            });
+        }
+
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(input_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let supported = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| SUPPORTED_FORMATS.contains(&ext.to_lowercase().as_str()));
+            if supported && entry.file_type().await?.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    async fn compress_one(
+        &self,
+        input: PathBuf,
+        output_dir: &Path,
+        cancel: &CancellationToken,
+    ) -> ItemOutcome {
+        let mut compressed = false;
+        let work = self.compress_to_dir(&input, output_dir, &mut compressed);
+
+        // Dropping `work` on cancellation aborts its in-flight request
+        let result = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            result = work => Some(result),
+        };
+
+        ItemOutcome {
+            input,
+            result,
+            compressed,
+        }
+    }
+
+    /// Compress `input` into `output_dir`, setting `compressed` once the upload succeeded
+    async fn compress_to_dir(
+        &self,
+        input: &Path,
+        output_dir: &Path,
+        compressed: &mut bool,
+    ) -> Result<BatchSuccess> {
+        let file_name = input
+            .file_name()
+            .ok_or_else(|| TinifyError::InvalidOptions {
+                message: format!("`{}` has no file name", input.display()),
+            })?;
+        let output = output_dir.join(file_name);
+
+        let source = self.source_from_file(input).await?;
+        *compressed = true;
+        let input_size = tokio::fs::metadata(input).await?.len();
+        let data = source.to_buffer().await?;
+        tokio::fs::write(&output, &data).await?;
+
+        Ok(BatchSuccess {
+            input: input.to_path_buf(),
+            output,
+            input_size,
+            output_size: data.len() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_batch_report_with_mixed_inputs() {
+        let mut server = mockito::Server::new_async().await;
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let _output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("tiny")
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let image = input_dir.path().join("photo.png");
+        let text = input_dir.path().join("notes.txt");
+        std::fs::write(&image, vec![0u8; 100]).unwrap();
+        std::fs::write(&text, "not an image").unwrap();
+        let missing = input_dir.path().join("missing.png");
+
+        let report = client
+            .compress_files([&image, &text, &missing], output_dir.path())
+            .await
+            .unwrap();
+
+        assert_eq!(report.processed(), 3);
+        assert!(report.has_failures());
+        assert_eq!(report.compressions, 1);
+        assert_eq!(report.bytes_saved(), 96);
+        assert_eq!(
+            report.succeeded[0].output,
+            output_dir.path().join("photo.png")
+        );
+        assert_eq!(std::fs::read(&report.succeeded[0].output).unwrap(), b"tiny");
+
+        let kinds: Vec<_> = report.failed.iter().map(|f| f.error.kind).collect();
+        assert_eq!(kinds, ["UnsupportedFormat", "FileNotFound"]);
+        assert_eq!(report.failed[0].input, text);
+
+        let summary = report.to_string();
+        assert!(summary.starts_with(
+            "Processed 3 files: 1 succeeded, 2 failed, 96 bytes saved, 1 compressions used"
+        ));
+        assert!(summary.contains("notes.txt: Unsupported file format: txt"));
+
+        // Directory mode skips the non-image file
+        let report = client
+            .compress_dir(input_dir.path(), output_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(report.processed(), 1);
+        assert!(!report.has_failures());
+    }
+
+    #[tokio::test]
+    async fn test_dir_stream_matches_batch_report() {
+        let mut server = mockito::Server::new_async().await;
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .match_body(mockito::Matcher::Regex("^image".to_string()))
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let _rejected = server
+            .mock("POST", "/shrink")
+            .match_body("corrupt")
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Input is corrupt"}"#)
+            .create_async()
+            .await;
+        let _output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("tiny")
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        for (name, body) in [
+            ("a.png", "image a"),
+            ("b.jpg", "corrupt"),
+            ("c.webp", "image c"),
+        ] {
+            std::fs::write(input_dir.path().join(name), body).unwrap();
+        }
+
+        let mut streamed: Vec<_> = client
+            .compress_dir_stream(input_dir.path(), output_dir.path())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        streamed.sort_by(|a, b| a.input.cmp(&b.input));
+
+        let report = client
+            .compress_dir(input_dir.path(), output_dir.path())
+            .await
+            .unwrap();
+
+        let succeeded: Vec<_> = streamed
+            .iter()
+            .filter_map(|item| item.result.as_ref().ok().cloned())
+            .collect();
+        assert_eq!(succeeded, report.succeeded);
+        let failed: Vec<_> = streamed
+            .iter()
+            .filter_map(|item| {
+                let err = item.result.as_ref().err()?;
+                Some((item.input.clone(), err.to_snapshot()))
+            })
+            .collect();
+        assert_eq!(
+            failed,
+            report
+                .failed
+                .iter()
+                .map(|f| (f.input.clone(), f.error.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(succeeded.len(), 2);
+        assert_eq!(failed.len(), 1);
+        let compressions = streamed.iter().filter(|item| item.compressed).count();
+        assert_eq!(compressions as u32, report.compressions);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_batch_reports_partial_results() {
+        let mut server = mockito::Server::new_async().await;
+        for name in ["fast", "slow"] {
+            server
+                .mock("POST", "/shrink")
+                .match_body(name)
+                .with_status(201)
+                .with_header("Location", &format!("{}/output/{}", server.url(), name))
+                .create_async()
+                .await;
+        }
+        let _fast = server
+            .mock("GET", "/output/fast")
+            .with_status(200)
+            .with_body("f")
+            .create_async()
+            .await;
+        let _slow = server
+            .mock("GET", "/output/slow")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_secs(2));
+                w.write_all(b"s")
+            })
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let fast = input_dir.path().join("fast.png");
+        let slow = input_dir.path().join("slow.png");
+        std::fs::write(&fast, "fast").unwrap();
+        std::fs::write(&slow, "slow").unwrap();
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let report = client
+            .compress_files_cancellable([&fast, &slow], output_dir.path(), &cancel)
+            .await
+            .unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.succeeded[0].input, fast);
+        assert_eq!(report.cancelled, vec![slow]);
+        assert_eq!(report.compressions, 2);
+        assert!(report.was_cancelled());
+        assert!(report.to_string().ends_with(", 1 cancelled"));
+
+        // Everything pending is cancelled once the token has fired
+        let report = client
+            .compress_files_cancellable([&fast], output_dir.path(), &cancel)
+            .await
+            .unwrap();
+        assert_eq!(report.cancelled.len(), 1);
+        assert_eq!(report.compressions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_shares_retry_budget() {
+        let mut server = mockito::Server::new_async().await;
+        // One attempt per file plus the two retries of the shared budget
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(503)
+            .expect(5)
+            .create_async()
+            .await;
+
+        let budget = crate::RetryBudget::new(2);
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .retry_config(crate::RetryConfig {
+                max_attempts: 5,
+                base_delay: std::time::Duration::from_millis(10),
+                max_delay: std::time::Duration::from_millis(10),
+                backoff_factor: 1.0,
+                max_total_duration: None,
+            })
+            .build()
+            .unwrap()
+            .with_request_options(crate::RequestOptions::new().retry_budget(budget.clone()));
+
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = ["a.png", "b.png", "c.png"]
+            .iter()
+            .map(|name| input_dir.path().join(name))
+            .collect();
+        for file in &files {
+            std::fs::write(file, "image").unwrap();
+        }
+
+        let report = client
+            .compress_files(&files, output_dir.path())
+            .await
+            .unwrap();
+
+        assert_eq!(report.failed.len(), 3);
+        assert_eq!(budget.remaining(), 0);
+        shrink.assert_async().await;
+    }
+}
@@ -0,0 +1,129 @@
+//! Synchronous API for callers that don't run a Tokio runtime
+//!
+//! Each call drives the async client on a shared current-thread runtime, which makes
+//! these functions usable from plain threads and FFI entry points. They must not be
+//! called from within a Tokio runtime; use the async API there instead.
+
+use crate::error::{Result, TinifyError};
+use crate::Tinify;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+/// Compress image data with a new client for `api_key`
+///
+/// All blocking calls share one current-thread runtime, so calls made concurrently
+/// from several threads are serialized and run one after another.
+///
+/// # Examples
+///
+/// ```no_run
+/// let image = std::fs::read("input.png").unwrap();
+/// let compressed = tinify::blocking::compress("your-api-key", &image)?;
+/// # Ok::<(), tinify::TinifyError>(())
+/// ```
+pub fn compress(api_key: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let client = Tinify::new(api_key.to_string())?;
+    compress_with(&client, data)
+}
+
+/// Compress image data with an existing client
+///
+/// Reusing one client across calls shares its rate limiter and connection settings.
+pub fn compress_with(client: &Tinify, data: &[u8]) -> Result<Vec<u8>> {
+    block_on(async {
+        let source = client.source_from_buffer(data.to_vec()).await?;
+        source.to_buffer().await
+    })
+}
+
+fn block_on<F: std::future::Future<Output = Result<T>>, T>(future: F) -> Result<T> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err(TinifyError::InvalidOptions {
+            message: "tinify::blocking cannot be used from within a Tokio runtime, use the async API instead".to_string(),
+        });
+    }
+
+    runtime()?.block_on(future)
+}
+
+/// Runtime shared by all blocking calls, created on first use
+///
+/// A client's pooled connections belong to the runtime that opened them, so every
+/// call has to run on the same one for a reused client to keep working.
+fn runtime() -> Result<&'static Runtime> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_compress() {
+        let mut server = mockito::Server::new();
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .match_body("image")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create();
+        let _output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("tiny")
+            .create();
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        assert_eq!(compress_with(&client, b"image").unwrap(), b"tiny");
+    }
+
+    #[test]
+    fn test_blocking_client_reused_across_calls() {
+        let mut server = mockito::Server::new();
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .expect(2)
+            .create();
+        let output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("tiny")
+            .expect(2)
+            .create();
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        assert_eq!(compress_with(&client, b"first").unwrap(), b"tiny");
+        assert_eq!(compress_with(&client, b"second").unwrap(), b"tiny");
+        shrink.assert();
+        output.assert();
+    }
+
+    #[tokio::test]
+    async fn test_blocking_inside_runtime_errors() {
+        let client = Tinify::new("test-key".to_string()).unwrap();
+        let result = compress_with(&client, b"image");
+        assert!(matches!(
+            result,
+            Err(TinifyError::InvalidOptions { message }) if message.contains("Tokio runtime")
+        ));
+    }
+}
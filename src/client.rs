@@ -1,23 +1,124 @@
+use crate::config::{duration_millis, option_duration_millis};
 use crate::error::{Result, TinifyError};
+#[cfg(feature = "record")]
+use crate::record::{RecordSink, Recorder};
 use base64::Engine;
-use governor::{Quota, RateLimiter};
+use futures_util::{future::BoxFuture, StreamExt};
+use governor::{
+    clock::DefaultClock,
+    middleware::StateInformationMiddleware,
+    state::{InMemoryState, NotKeyed},
+    Quota, RateLimiter,
+};
+use md5::{Digest, Md5};
 use nonzero_ext::*;
-use reqwest::{Client as ReqwestClient, Response};
-use std::{num::NonZeroU32, sync::Arc, time::Duration};
-use tokio::io::AsyncRead;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    Certificate,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Client as ReqwestClient, Response,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::net::{IpAddr, SocketAddr};
+use std::{
+    collections::HashMap,
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+#[cfg(not(target_arch = "wasm32"))]
 use tokio_util::io::ReaderStream;
-use tracing::{debug, info, instrument, warn};
+use tracing::{debug, error, info, instrument, warn, Instrument};
 
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const DEFAULT_BASE_URL: &str = "https://api.tinify.com";
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 const DEFAULT_MAX_RETRIES: u32 = 3;
 const DEFAULT_RATE_LIMIT: u32 = 100; // requests per minute
+const MAX_ERROR_BODY_CHARS: usize = 200;
+const DEFAULT_MAX_DIMENSION: u32 = 10000;
+const DEFAULT_STREAM_CHUNK_SIZE: usize = 4096;
+/// Operation bodies are a few hundred bytes; anything near this is a mistake
+const DEFAULT_MAX_OPERATION_BODY_SIZE: usize = 64 * 1024;
+/// Headers the client sets per request, which default headers would duplicate
+const MANAGED_HEADERS: &[&str] = &[
+    "user-agent",
+    "content-type",
+    "content-length",
+    "range",
+    "x-request-id",
+    "content-md5",
+    "accept-encoding",
+];
+/// Keys from examples and docs that get copied into real code
+const PLACEHOLDER_API_KEYS: &[&str] = &[
+    "XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq",
+    "your-api-key",
+    "YOUR_API_KEY",
+];
 
-#[derive(Debug, Clone)]
+/// Retry behaviour for failed requests
+///
+/// The delay before attempt `n + 1` is `base_delay * backoff_factor^(n - 1)`, capped at
+/// `max_delay`. Only connection errors, server errors and rate limiting are retried.
+/// Building a client with an invalid config fails, see [`RetryConfig::validate`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RetryConfig {
     pub max_attempts: u32,
+    #[serde(with = "duration_millis")]
     pub base_delay: Duration,
+    #[serde(with = "duration_millis")]
     pub max_delay: Duration,
     pub backoff_factor: f64,
+    /// Upper bound on the total time spent on a request including all retries.
+    ///
+    /// Before sleeping for the next retry, the elapsed time plus the delay is compared
+    /// against this budget; if it would be exceeded, the last error is returned instead.
+    /// A single in-flight attempt is still bounded only by the client timeout.
+    /// `None` means no limit beyond `max_attempts`.
+    #[serde(with = "option_duration_millis")]
+    pub max_total_duration: Option<Duration>,
+}
+
+impl RetryConfig {
+    /// Check that the settings describe a sensible backoff
+    ///
+    /// Requires at least one attempt, `base_delay <= max_delay` and a finite
+    /// `backoff_factor` of at least 1.0, since a smaller factor shrinks the delays
+    /// toward zero instead of backing off.
+    pub fn validate(&self) -> Result<()> {
+        let problem = if self.max_attempts == 0 {
+            Some("max_attempts must be at least 1".to_string())
+        } else if self.base_delay > self.max_delay {
+            Some(format!(
+                "base_delay ({:?}) must not exceed max_delay ({:?})",
+                self.base_delay, self.max_delay
+            ))
+        } else if !self.backoff_factor.is_finite() || self.backoff_factor < 1.0 {
+            Some(format!(
+                "backoff_factor must be at least 1.0, got {}",
+                self.backoff_factor
+            ))
+        } else {
+            None
+        };
+
+        match problem {
+            Some(message) => Err(TinifyError::InvalidOptions {
+                message: format!("Invalid retry config: {}", message),
+            }),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Default for RetryConfig {
@@ -27,11 +128,33 @@ impl Default for RetryConfig {
             base_delay: Duration::from_millis(100),
             max_delay: Duration::from_secs(10),
             backoff_factor: 2.0,
+            max_total_duration: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Waits out the delay between retry attempts
+///
+/// The default [`TokioSleeper`] uses `tokio::time::sleep`. Supply another
+/// implementation via [`ClientBuilder::sleeper`] to run the backoff on a different
+/// timer, or to record delays in tests without actually waiting. HTTP requests
+/// themselves still go through reqwest.
+pub trait Sleeper: Send + Sync + std::fmt::Debug {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// [`Sleeper`] backed by `tokio::time::sleep`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSleeper;
+
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RateLimit {
     pub requests_per_minute: u32,
     pub burst_capacity: u32,
@@ -46,19 +169,380 @@ impl Default for RateLimit {
     }
 }
 
-#[derive(Debug)]
+/// Id sent as `X-Request-Id`, stored in the response extensions so results can report it
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub(crate) struct RequestId(pub(crate) String);
+
+/// Cumulative request and traffic counters of a client
+///
+/// Byte counts cover request and response bodies only, not headers. Clones of a
+/// `Tinify` handle share the same counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// HTTP requests sent, including retries
+    pub requests: u64,
+    /// Requests that were retried after a retryable failure
+    pub retries: u64,
+    /// Bytes of request bodies uploaded
+    pub bytes_up: u64,
+    /// Bytes of response bodies downloaded
+    pub bytes_down: u64,
+}
+
+/// Shared atomic counters behind [`ClientStats`]
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    bytes_up: AtomicU64,
+    bytes_down: AtomicU64,
+}
+
+impl StatsCounters {
+    fn add_bytes_up(&self, bytes: usize) {
+        self.bytes_up.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_bytes_down(&self, bytes: usize) {
+        self.bytes_down.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ClientStats {
+        ClientStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            bytes_up: self.bytes_up.load(Ordering::Relaxed),
+            bytes_down: self.bytes_down.load(Ordering::Relaxed),
+        }
+    }
+}
+
+type QuotaCallback = dyn Fn(u32, u32) + Send + Sync;
+
+/// Calls back once when the monthly compression count crosses a fraction of the limit
+struct QuotaThreshold {
+    fraction: f64,
+    /// Configured monthly limit; the `Compression-Limit` header is used when unset
+    limit: Option<u32>,
+    callback: Box<QuotaCallback>,
+    fired: AtomicBool,
+}
+
+impl std::fmt::Debug for QuotaThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuotaThreshold")
+            .field("fraction", &self.fraction)
+            .field("limit", &self.limit)
+            .field("fired", &self.fired)
+            .finish_non_exhaustive()
+    }
+}
+
+impl QuotaThreshold {
+    fn observe(&self, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u32>().ok())
+        };
+        let Some(count) = header("Compression-Count") else {
+            return;
+        };
+        let Some(limit) = self.limit.or_else(|| header("Compression-Limit")) else {
+            return;
+        };
+
+        if f64::from(count) >= self.fraction * f64::from(limit)
+            && !self.fired.swap(true, Ordering::Relaxed)
+        {
+            warn!(
+                count,
+                limit,
+                "Compression count crossed the quota threshold of {:.0}%",
+                self.fraction * 100.0
+            );
+            (self.callback)(count, limit);
+        }
+    }
+}
+
+/// Body of a POST request, tagged with how it is sent
+#[derive(Debug, Clone)]
+pub enum RequestBody {
+    /// Serialized JSON, sent with `Content-Type: application/json`
+    Json(Vec<u8>),
+    /// Raw image data, sent with `content_type` if given or no `Content-Type` otherwise,
+    /// leaving format detection to the server
+    Image {
+        data: Vec<u8>,
+        content_type: Option<String>,
+    },
+}
+
+impl RequestBody {
+    /// Serialize `value` as a JSON body
+    pub fn json<T: serde::Serialize + ?Sized>(value: &T) -> Result<Self> {
+        Ok(RequestBody::Json(serde_json::to_vec(value)?))
+    }
+
+    /// Raw image body without an explicit content type
+    pub fn image(data: Vec<u8>) -> Self {
+        RequestBody::Image {
+            data,
+            content_type: None,
+        }
+    }
+
+    fn content_type(&self) -> Option<&str> {
+        match self {
+            RequestBody::Json(_) => Some("application/json"),
+            RequestBody::Image { content_type, .. } => content_type.as_deref(),
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            RequestBody::Json(data) | RequestBody::Image { data, .. } => data,
+        }
+    }
+}
+
+/// Per-operation settings layered on top of the client configuration
+///
+/// Applied with [`Client::with_request_options`], which returns a handle whose
+/// requests all follow these settings.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Point in time by which every request, including rate limiting, retries and
+    /// reading the response body, must have finished
+    pub deadline: Option<Instant>,
+    /// Make a single attempt per request regardless of the client's [`RetryConfig`]
+    pub no_retry: bool,
+    /// Retries shared by every request made with these options, on top of the
+    /// per-request limits of the [`RetryConfig`]
+    pub retry_budget: Option<RetryBudget>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn no_retry(mut self) -> Self {
+        self.no_retry = true;
+        self
+    }
+}
+
+/// Number of retries shared between requests
+///
+/// Each retry takes one from the budget; once it is spent, a failing request returns
+/// its error instead of retrying, even if its [`RetryConfig`] would allow more
+/// attempts. Clones share the same budget, so a batch whose files all fail can't
+/// make more than one attempt per file plus the budget.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    remaining: Arc<AtomicU32>,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            remaining: Arc::new(AtomicU32::new(retries)),
+        }
+    }
+
+    /// Retries left in the budget
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::Relaxed)
+    }
+
+    /// Take one retry from the budget, or return `false` if it is spent
+    fn try_take(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+/// `Send` on native targets, where requests may run on a multi-threaded runtime
+///
+/// Futures built on the browser's fetch API are not `Send`, so on wasm32 this is
+/// implemented for every type.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + ?Sized> MaybeSend for T {}
+#[cfg(target_arch = "wasm32")]
+pub(crate) trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// IP version to restrict outgoing connections to
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    V4,
+    V6,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl IpVersion {
+    fn matches(self, addr: &SocketAddr) -> bool {
+        match self {
+            IpVersion::V4 => addr.is_ipv4(),
+            IpVersion::V6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// DNS resolver that only returns addresses of one IP version
+#[cfg(not(target_arch = "wasm32"))]
+struct IpVersionResolver(IpVersion);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Resolve for IpVersionResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let version = self.0;
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| version.matches(addr))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no {:?} address", host, version).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Registry of rate limiters shared between clients with the same API key
+///
+/// Clones of a `Tinify` handle always share one limiter, but clients built separately
+/// get independent limiters and can collectively exceed the account's real rate.
+/// Building clients with the same registry makes every client using a given API key
+/// draw from one limiter. The first client registered for a key decides its
+/// [`RateLimit`]; later clients reuse that limiter and their own setting is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use tinify::{RateLimiterRegistry, Tinify};
+///
+/// let registry = RateLimiterRegistry::global();
+/// let first = Tinify::builder().api_key("key").rate_limiter_registry(registry).build()?;
+/// let second = Tinify::builder().api_key("key").rate_limiter_registry(registry).build()?;
+/// # Ok::<(), tinify::TinifyError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiterRegistry {
+    limiters: Arc<Mutex<HashMap<String, Arc<Limiter>>>>,
+}
+
+impl RateLimiterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide registry
+    pub fn global() -> &'static RateLimiterRegistry {
+        static GLOBAL: OnceLock<RateLimiterRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(RateLimiterRegistry::new)
+    }
+
+    fn limiter_for(&self, api_key: &str, rate_limit: &RateLimit) -> Arc<Limiter> {
+        let mut limiters = self.limiters.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(
+            limiters
+                .entry(api_key.to_string())
+                .or_insert_with(|| Client::create_rate_limiter(rate_limit)),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Client {
     http_client: ReqwestClient,
     api_key: String,
     app_identifier: Option<String>,
+    base_url: String,
+    default_headers: HeaderMap,
+    send_request_id: bool,
+    content_md5: bool,
+    resume_downloads: bool,
+    dry_run: bool,
+    strict: bool,
+    download_timeout: Option<Duration>,
+    max_dimension: u32,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    stream_chunk_size: usize,
+    max_operation_body_size: usize,
     retry_config: RetryConfig,
-    rate_limiter: Arc<
-        RateLimiter<
-            governor::state::direct::NotKeyed,
-            governor::state::InMemoryState,
-            governor::clock::DefaultClock,
-        >,
-    >,
+    sleeper: Arc<dyn Sleeper>,
+    quota_threshold: Option<Arc<QuotaThreshold>>,
+    request_options: RequestOptions,
+    rate_limiter: Arc<Limiter>,
+    stats: Arc<StatsCounters>,
+    #[cfg(feature = "record")]
+    recorder: Option<Recorder>,
+}
+
+/// Token-bucket rate limiter with a best-effort view of the remaining budget
+#[derive(Debug)]
+struct Limiter {
+    inner: RateLimiter<NotKeyed, InMemoryState, DefaultClock, StateInformationMiddleware>,
+    quota: Quota,
+    /// Settings the quota was built from, after replacing zeros with the defaults
+    rate_limit: RateLimit,
+    /// Remaining burst capacity reported by the last granted permit, and when it was granted
+    last_grant: Mutex<Option<(u32, Instant)>>,
+}
+
+impl Limiter {
+    fn new(quota: Quota, rate_limit: RateLimit) -> Self {
+        Self {
+            inner: RateLimiter::direct(quota).with_middleware(),
+            quota,
+            rate_limit,
+            last_grant: Mutex::new(None),
+        }
+    }
+
+    async fn acquire(&self) {
+        let snapshot = self.inner.until_ready().await;
+        let mut last_grant = self.last_grant.lock().unwrap_or_else(|e| e.into_inner());
+        *last_grant = Some((snapshot.remaining_burst_capacity(), Instant::now()));
+    }
+
+    fn available(&self) -> usize {
+        let burst = self.quota.burst_size().get() as usize;
+        let last_grant = *self.last_grant.lock().unwrap_or_else(|e| e.into_inner());
+
+        match last_grant {
+            None => burst,
+            Some((remaining, granted_at)) => {
+                let interval = self.quota.replenish_interval().as_nanos().max(1);
+                let replenished = granted_at.elapsed().as_nanos() / interval;
+                (remaining as usize)
+                    .saturating_add(replenished.min(burst as u128) as usize)
+                    .min(burst)
+            }
+        }
+    }
 }
 
 impl Client {
@@ -70,27 +554,99 @@ impl Client {
         ClientBuilder::new()
     }
 
-    fn create_http_client(timeout: Duration) -> Result<ReqwestClient> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn create_http_client(config: &ClientBuilder) -> Result<ReqwestClient> {
+        let mut builder = ReqwestClient::builder()
+            .timeout(config.timeout)
+            .danger_accept_invalid_certs(config.accept_invalid_certs)
+            .gzip(config.decompress)
+            .brotli(config.decompress)
+            .local_address(config.local_address);
+        for certificate in &config.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
+        }
+        if let Some(version) = config.ip_version {
+            builder = builder.dns_resolver(Arc::new(IpVersionResolver(version)));
+        }
+        builder.build().map_err(TinifyError::ConnectionError)
+    }
+
+    /// The browser's fetch API manages TLS, connections and decompression on wasm32
+    #[cfg(target_arch = "wasm32")]
+    fn create_http_client(_config: &ClientBuilder) -> Result<ReqwestClient> {
         ReqwestClient::builder()
-            .timeout(timeout)
             .build()
             .map_err(TinifyError::ConnectionError)
     }
 
-    fn create_rate_limiter(
-        rate_limit: &RateLimit,
-    ) -> Arc<
-        RateLimiter<
-            governor::state::direct::NotKeyed,
-            governor::state::InMemoryState,
-            governor::clock::DefaultClock,
-        >,
-    > {
+    fn create_default_headers(headers: &[(String, String)]) -> Result<HeaderMap> {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                TinifyError::InvalidOptions {
+                    message: format!("invalid header name `{}`", name),
+                }
+            })?;
+            if header_name == AUTHORIZATION {
+                return Err(TinifyError::InvalidOptions {
+                    message:
+                        "the Authorization header is set from the API key and cannot be overridden"
+                            .to_string(),
+                });
+            }
+            if MANAGED_HEADERS.contains(&header_name.as_str()) {
+                return Err(TinifyError::InvalidOptions {
+                    message: format!(
+                        "the {} header is set by the client and cannot be a default header",
+                        name
+                    ),
+                });
+            }
+            let header_value =
+                HeaderValue::from_str(value).map_err(|_| TinifyError::InvalidOptions {
+                    message: format!("invalid value for header `{}`", name),
+                })?;
+            map.append(header_name, header_value);
+        }
+        Ok(map)
+    }
+
+    /// The identifier is sent as `User-Agent`, so it must be a legal header value
+    fn validate_app_identifier(identifier: &str) -> Result<()> {
+        HeaderValue::from_str(identifier)
+            .map(|_| ())
+            .map_err(|_| TinifyError::InvalidOptions {
+                message: format!(
+                    "app identifier {:?} is not a valid header value; use visible ASCII \
+                     characters without line breaks",
+                    identifier
+                ),
+            })
+    }
+
+    fn create_rate_limiter(rate_limit: &RateLimit) -> Arc<Limiter> {
         let requests_per_minute =
             NonZeroU32::new(rate_limit.requests_per_minute).unwrap_or(nonzero!(100u32));
         let burst_capacity = NonZeroU32::new(rate_limit.burst_capacity).unwrap_or(nonzero!(10u32));
         let quota = Quota::per_minute(requests_per_minute).allow_burst(burst_capacity);
-        Arc::new(RateLimiter::direct(quota))
+        let effective = RateLimit {
+            requests_per_minute: requests_per_minute.get(),
+            burst_capacity: burst_capacity.get(),
+        };
+        Arc::new(Limiter::new(quota, effective))
+    }
+
+    fn truncate_error_body(body: &str, max_chars: usize) -> String {
+        match body.char_indices().nth(max_chars) {
+            Some((end, _)) => format!("{}... (truncated)", &body[..end]),
+            None => body.to_string(),
+        }
     }
 
     #[instrument(skip(response), fields(status = %response.status()))]
@@ -99,74 +655,171 @@ impl Client {
             return Ok(response);
         }
 
-        let status = response.status().as_u16();
+        let status = response.status();
 
         // Get headers before consuming response
         let retry_after = response
             .headers()
             .get("Retry-After")
             .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(60);
+            .and_then(|s| s.parse().ok());
 
-        let error_body = response
-            .json::<serde_json::Value>()
-            .await
-            .unwrap_or_default();
+        let body_text = response.text().await.unwrap_or_default();
+        Err(Self::error_from_body(status, retry_after, &body_text))
+    }
+
+    /// Error for a non-success `status` answered with `body_text`
+    fn error_from_body(
+        status: reqwest::StatusCode,
+        retry_after: Option<u64>,
+        body_text: &str,
+    ) -> TinifyError {
+        // Gateways and proxies may answer with HTML or plain text, so don't assume JSON
+        let error_body = serde_json::from_str::<serde_json::Value>(body_text).unwrap_or_default();
 
-        let message = error_body
-            .get("message")
-            .and_then(|m| m.as_str())
-            .unwrap_or("Unknown error")
-            .to_string();
+        let message = match error_body.get("message").and_then(|m| m.as_str()) {
+            Some(message) => message.to_string(),
+            None if !body_text.trim().is_empty() => {
+                Self::truncate_error_body(body_text.trim(), MAX_ERROR_BODY_CHARS)
+            }
+            None => "Unknown error".to_string(),
+        };
 
         let error_type = error_body
             .get("error")
             .and_then(|e| e.as_str())
             .map(String::from);
 
-        debug!("API error response: status={}, message={}", status, message);
+        debug!(
+            "API error response: status={}, message={}",
+            status.as_u16(),
+            message
+        );
 
-        match status {
-            401 => {
-                if message.contains("credentials") {
-                    Err(TinifyError::InvalidApiKey)
-                } else {
-                    Err(TinifyError::AccountError {
-                        message,
-                        error_type,
-                        status: Some(status),
-                    })
-                }
-            }
-            429 => {
-                if message.contains("quota") {
-                    Err(TinifyError::QuotaExceeded)
-                } else {
-                    Err(TinifyError::RateLimitExceeded { retry_after })
+        let mut err = TinifyError::from_status(status, message, error_type);
+        match &mut err {
+            TinifyError::RateLimitExceeded {
+                retry_after: seconds,
+                ..
+            } => {
+                if let Some(retry_after) = retry_after {
+                    *seconds = retry_after;
                 }
             }
-            400..=499 => Err(TinifyError::ClientError {
+            TinifyError::QuotaExceeded {
                 message,
                 error_type,
-                status: Some(status),
-            }),
-            500..=599 => Err(TinifyError::ServerError {
-                message,
-                error_type,
-                status: Some(status),
-            }),
-            _ => Err(TinifyError::UnknownError { message }),
+            } => {
+                error!(
+                    error_type = error_type.as_deref(),
+                    "Monthly compression quota exceeded, no further requests will succeed until it resets: {}",
+                    message
+                );
+            }
+            _ => {}
+        }
+        err
+    }
+
+    /// Generate a request id if enabled and record it on the current span
+    fn next_request_id(&self) -> Option<String> {
+        if !self.send_request_id {
+            return None;
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        tracing::Span::current().record("request_id", id.as_str());
+        Some(id)
+    }
+
+    /// Synthetic successful response returned instead of sending a request in dry-run mode
+    #[cfg(not(target_arch = "wasm32"))]
+    fn dry_run_response(&self, url: &str) -> Result<Response> {
+        debug!("Dry run: skipping request to {}", url);
+
+        let location = if url == self.shrink_endpoint() {
+            format!("{}/output/dry-run", self.base_url)
+        } else {
+            url.to_string()
+        };
+
+        let response = http::Response::builder()
+            .status(if url == self.shrink_endpoint() {
+                201
+            } else {
+                200
+            })
+            .header("Location", location)
+            .header("Content-Length", "0")
+            .body(Vec::new())
+            .expect("static dry-run response is valid");
+        Ok(Response::from(response))
+    }
+
+    /// Responses can only come from fetch on wasm32, so dry-run mode is unavailable
+    #[cfg(target_arch = "wasm32")]
+    fn dry_run_response(&self, url: &str) -> Result<Response> {
+        Err(TinifyError::InvalidOptions {
+            message: format!("Dry run is not supported on wasm32 (request to {})", url),
+        })
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Fail with `message` in strict mode, otherwise only log it as a warning
+    pub(crate) fn soft_violation(&self, message: String) -> Result<()> {
+        if self.strict {
+            return Err(TinifyError::InvalidOptions { message });
+        }
+        warn!("{}", message);
+        Ok(())
+    }
+
+    /// Largest width or height accepted when validating resize options
+    pub fn max_dimension(&self) -> u32 {
+        self.max_dimension
+    }
+
+    /// Tag `response` with its request id and the stats counters its body counts toward
+    ///
+    /// Responses have no extensions on wasm32, so there results carry neither.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut, unused_variables))]
+    fn annotate_response(&self, mut response: Response, request_id: Option<String>) -> Response {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(id) = request_id {
+                response.extensions_mut().insert(RequestId(id));
+            }
+            response.extensions_mut().insert(Arc::clone(&self.stats));
+        }
+        if let Some(threshold) = &self.quota_threshold {
+            threshold.observe(response.headers());
         }
+        response
     }
 
-    fn add_common_headers(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    fn add_common_headers(
+        &self,
+        request: reqwest::RequestBuilder,
+        request_id: Option<&str>,
+    ) -> reqwest::RequestBuilder {
         let auth = format!("api:{}", self.api_key);
         let auth_header = format!(
             "Basic {}",
             Engine::encode(&base64::engine::general_purpose::STANDARD, auth)
         );
-        let request = request.header("Authorization", auth_header);
+        let mut request = request
+            .header("Authorization", auth_header)
+            .headers(self.default_headers.clone());
+
+        if let Some(id) = request_id {
+            request = request.header("X-Request-Id", id);
+        }
 
         match &self.app_identifier {
             Some(app_id) => request.header("User-Agent", app_id),
@@ -174,52 +827,163 @@ impl Client {
         }
     }
 
+    /// Send `request`, handing the exchange to the recorder in record mode
+    async fn send(&self, request: reqwest::RequestBuilder) -> reqwest::Result<Response> {
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &self.recorder {
+            return recorder.send(request).await;
+        }
+        request.send().await
+    }
+
+    /// Wait until the rate limiter grants a permit for one request.
+    ///
+    /// Each call consumes exactly one cell from the limiter.
     #[instrument(skip(self))]
     async fn check_rate_limit(&self) -> Result<()> {
-        if self.rate_limiter.check().is_err() {
-            warn!("Rate limit exceeded, waiting for next available slot");
-            self.rate_limiter.until_ready().await;
-        }
+        self.rate_limiter.acquire().await;
         Ok(())
     }
 
+    /// Copy of this client whose requests follow `options`
+    ///
+    /// The copy shares the connection pool, rate limiter and stats with this client.
+    pub fn with_request_options(&self, options: RequestOptions) -> Client {
+        Client {
+            request_options: options,
+            ..self.clone()
+        }
+    }
+
+    /// Run `operation`, failing with [`TinifyError::Timeout`] if the deadline passes first
+    async fn within_deadline<T>(
+        &self,
+        operation: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some(deadline) = self.request_options.deadline else {
+            return operation.await;
+        };
+
+        let started = Instant::now();
+        tokio::time::timeout_at(deadline.into(), operation)
+            .await
+            .unwrap_or_else(|_| {
+                warn!("Deadline passed after {:?}", started.elapsed());
+                Err(TinifyError::Timeout {
+                    elapsed: started.elapsed(),
+                })
+            })
+    }
+
+    /// Attempts allowed per request, or per download when resuming
+    fn max_attempts(&self) -> u32 {
+        if self.request_options.no_retry {
+            1
+        } else {
+            self.retry_config.max_attempts
+        }
+    }
+
+    /// Timeout for a single request: `timeout` if given, capped by the time left
+    /// until the deadline
+    ///
+    /// Because the request timeout also covers reading the body, this bounds
+    /// downloads that happen after the request future has resolved.
+    fn request_timeout(&self, timeout: Option<Duration>) -> Option<Duration> {
+        let remaining = self
+            .request_options
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        }
+    }
+
     async fn execute_request<F, Fut>(&self, request_fn: F) -> Result<Response>
     where
-        F: Fn() -> Fut + Send,
-        Fut: std::future::Future<Output = Result<Response>> + Send,
+        F: Fn() -> Fut + MaybeSend,
+        Fut: std::future::Future<Output = Result<Response>> + MaybeSend,
+    {
+        self.within_deadline(self.execute_attempts(request_fn))
+            .await
+    }
+
+    async fn execute_attempts<F, Fut>(&self, request_fn: F) -> Result<Response>
+    where
+        F: Fn() -> Fut + MaybeSend,
+        Fut: std::future::Future<Output = Result<Response>> + MaybeSend,
     {
         let mut delay = self.retry_config.base_delay;
+        let started = Instant::now();
 
-        for attempt in 1..=self.retry_config.max_attempts {
+        let max_attempts = self.max_attempts();
+        for attempt in 1..=max_attempts {
             self.check_rate_limit().await?;
+            self.stats.requests.fetch_add(1, Ordering::Relaxed);
 
-            match request_fn().await {
+            let span = tracing::info_span!("attempt", attempt, max_attempts);
+            match request_fn().instrument(span).await {
                 Ok(response) => return Ok(response),
                 Err(err) => {
-                    if attempt == self.retry_config.max_attempts {
+                    // Includes QuotaExceeded, which retrying can't fix
+                    if !err.is_retryable() {
                         return Err(err);
                     }
 
-                    match &err {
-                        TinifyError::ConnectionError(_)
-                        | TinifyError::ServerError { .. }
-                        | TinifyError::RateLimitExceeded { .. } => {
+                    if attempt == max_attempts {
+                        return Err(Self::retries_exhausted(err, attempt, started));
+                    }
+
+                    if let Some(budget) = self.retry_config.max_total_duration {
+                        if started.elapsed() + delay > budget {
                             warn!(
-                                "Request failed (attempt {}/{}), retrying in {:?}: {}",
-                                attempt, self.retry_config.max_attempts, delay, err
+                                attempt,
+                                max_attempts,
+                                error_kind = err.kind(),
+                                "Retry budget of {:?} exhausted after {} attempts: {}",
+                                budget,
+                                attempt,
+                                err
                             );
-                            tokio::time::sleep(delay).await;
-
-                            delay = std::cmp::min(
-                                Duration::from_millis(
-                                    (delay.as_millis() as f64 * self.retry_config.backoff_factor)
-                                        as u64,
-                                ),
-                                self.retry_config.max_delay,
+                            return Err(Self::retries_exhausted(err, attempt, started));
+                        }
+                    }
+
+                    if let Some(retries) = &self.request_options.retry_budget {
+                        if !retries.try_take() {
+                            warn!(
+                                attempt,
+                                max_attempts,
+                                error_kind = err.kind(),
+                                "Shared retry budget spent after {} attempts: {}",
+                                attempt,
+                                err
                             );
+                            return Err(Self::retries_exhausted(err, attempt, started));
                         }
-                        _ => return Err(err),
                     }
+
+                    warn!(
+                        attempt,
+                        max_attempts,
+                        delay_ms = delay.as_millis() as u64,
+                        error_kind = err.kind(),
+                        "Request failed (attempt {}/{}), retrying in {:?}: {}",
+                        attempt,
+                        max_attempts,
+                        delay,
+                        err
+                    );
+                    self.sleeper.sleep(delay).await;
+                    self.stats.retries.fetch_add(1, Ordering::Relaxed);
+
+                    delay = std::cmp::min(
+                        Duration::from_millis(
+                            (delay.as_millis() as f64 * self.retry_config.backoff_factor) as u64,
+                        ),
+                        self.retry_config.max_delay,
+                    );
                 }
             }
         }
@@ -227,45 +991,246 @@ impl Client {
         unreachable!()
     }
 
+    /// Wrap the last error once it has been retried at least once
+    fn retries_exhausted(err: TinifyError, attempts: u32, started: Instant) -> TinifyError {
+        if attempts < 2 {
+            return err;
+        }
+        TinifyError::RetriesExhausted {
+            attempts,
+            elapsed: started.elapsed(),
+            source: Box::new(err),
+        }
+    }
+
     pub fn api_key(&self) -> &str {
         &self.api_key
     }
 
-    pub fn set_app_identifier(&mut self, app_identifier: String) {
-        self.app_identifier = Some(app_identifier);
+    /// Best-effort estimate of how many requests can be made right now without waiting
+    pub fn rate_limit_available(&self) -> usize {
+        self.rate_limiter.available()
     }
 
-    #[instrument(skip(self, body))]
-    pub async fn post<S: AsRef<str> + std::fmt::Debug>(
-        &self,
-        url: S,
-        body: Option<Vec<u8>>,
-    ) -> Result<Response> {
-        let url = url.as_ref();
-        info!("Making POST request to: {}", url);
+    /// Retry settings requests are made with
+    pub fn retry_config(&self) -> &RetryConfig {
+        &self.retry_config
+    }
 
-        self.execute_request(|| {
-            let request = self.http_client.post(url);
-            let mut request = self.add_common_headers(request);
+    /// Rate limit enforced by this client's limiter
+    ///
+    /// With a shared [`RateLimiterRegistry`] this is the limit the shared limiter was
+    /// created with, which may differ from the one passed to this client's builder.
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limiter.rate_limit
+    }
+
+    /// Snapshot of the request and traffic counters
+    pub fn stats(&self) -> ClientStats {
+        self.stats.snapshot()
+    }
+
+    /// Download the body at `url` into memory
+    ///
+    /// Uses the download timeout, if configured, instead of the client-wide timeout.
+    ///
+    /// When download resuming is enabled and the server advertises
+    /// `Accept-Ranges: bytes`, a connection dropped mid-body is resumed with a
+    /// `Range: bytes=N-` request instead of starting over, up to
+    /// `retry_config.max_attempts` times in total, or not at all with [`RequestOptions::no_retry`].
+    /// A resumed response whose `Content-Range` does not start where the received
+    /// bytes end is rejected rather than appended.
+    #[instrument(skip(self))]
+    pub async fn download<S: AsRef<str> + std::fmt::Debug>(&self, url: S) -> Result<Vec<u8>> {
+        self.within_deadline(self.download_body(url.as_ref())).await
+    }
+
+    async fn download_body(&self, url: &str) -> Result<Vec<u8>> {
+        // Resume offsets count decoded bytes, while a range applies to the encoded
+        // representation, so a resumable download must not be content-encoded
+        let headers: &[(&str, &str)] = if self.resume_downloads {
+            &[("Accept-Encoding", "identity")]
+        } else {
+            &[]
+        };
+        let response = self
+            .fetch(reqwest::Method::GET, url, self.download_timeout, headers)
+            .await?;
+
+        if !self.resume_downloads {
+            let bytes = response.bytes().await?;
+            self.stats.add_bytes_down(bytes.len());
+            return Ok(bytes.to_vec());
+        }
+
+        let supports_ranges = response
+            .headers()
+            .get("Accept-Ranges")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("bytes"));
+
+        let mut buffer = Vec::with_capacity(response.content_length().unwrap_or(0) as usize);
+        let mut stream = Box::pin(response.bytes_stream());
+        let mut attempt = 1;
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    self.stats.add_bytes_down(chunk.len());
+                    buffer.extend_from_slice(&chunk);
+                }
+                Err(err) => {
+                    if !supports_ranges || attempt >= self.max_attempts() {
+                        return Err(TinifyError::ConnectionError(err));
+                    }
+                    attempt += 1;
 
-            if let Some(ref body_data) = body {
-                if body_data.starts_with(b"{") || body_data.starts_with(b"[") {
-                    request = request.header("Content-Type", "application/json");
+                    warn!(
+                        "Download interrupted after {} bytes, resuming (attempt {}/{}): {}",
+                        buffer.len(),
+                        attempt,
+                        self.max_attempts(),
+                        err
+                    );
+
+                    let response = self.get_range(url, buffer.len() as u64).await?;
+                    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                        return Err(TinifyError::UnknownError {
+                            message: format!(
+                                "Server ignored range request while resuming download (status {})",
+                                response.status()
+                            ),
+                            status: Some(response.status().as_u16()),
+                        });
+                    }
+                    let start = Self::content_range_start(response.headers());
+                    if start != Some(buffer.len() as u64) {
+                        return Err(TinifyError::UnknownError {
+                            message: format!(
+                                "Server answered range request for byte {} with Content-Range {:?} \
+                                 while resuming download",
+                                buffer.len(),
+                                response.headers().get("Content-Range")
+                            ),
+                            status: Some(response.status().as_u16()),
+                        });
+                    }
+                    stream = Box::pin(response.bytes_stream());
                 }
-                request = request.body(body_data.clone());
             }
+        }
+
+        Ok(buffer)
+    }
+
+    /// First byte position of a `Content-Range: bytes START-END/TOTAL` header
+    fn content_range_start(headers: &HeaderMap) -> Option<u64> {
+        let range = headers.get("Content-Range")?.to_str().ok()?;
+        let (start, _) = range.trim().strip_prefix("bytes ")?.split_once('-')?;
+        start.trim().parse().ok()
+    }
+
+    async fn get_range(&self, url: &str, offset: u64) -> Result<Response> {
+        debug!("Requesting {} from byte offset {}", url, offset);
+
+        self.execute_request(|| {
+            let mut request = self.http_client.get(url);
+            if let Some(timeout) = self.request_timeout(self.download_timeout) {
+                request = request.timeout(timeout);
+            }
+            let request = self
+                .add_common_headers(request, None)
+                .header("Range", format!("bytes={}-", offset))
+                .header("Accept-Encoding", "identity");
 
             async move {
-                let response = request.send().await.map_err(TinifyError::ConnectionError)?;
+                let response = self
+                    .send(request)
+                    .await
+                    .map_err(TinifyError::ConnectionError)?;
                 Self::handle_error_response(response).await
             }
         })
         .await
     }
 
-    #[instrument(skip(self, stream))]
-    pub async fn post_stream<S: AsRef<str> + std::fmt::Debug, R>(
-        &self,
+    /// URL of the shrink endpoint used for uploads
+    pub fn shrink_endpoint(&self) -> String {
+        format!("{}/shrink", self.base_url)
+    }
+
+    pub fn set_app_identifier(&mut self, app_identifier: String) -> Result<()> {
+        Self::validate_app_identifier(&app_identifier)?;
+        self.app_identifier = Some(app_identifier);
+        Ok(())
+    }
+
+    #[instrument(skip(self, body), fields(version = VERSION, request_id = tracing::field::Empty))]
+    pub async fn post<S: AsRef<str> + std::fmt::Debug>(
+        &self,
+        url: S,
+        body: Option<RequestBody>,
+    ) -> Result<Response> {
+        let url = url.as_ref();
+        info!("Making POST request to: {}", url);
+        let request_id = self.next_request_id();
+
+        if let Some(RequestBody::Json(json)) = &body {
+            if json.len() > self.max_operation_body_size {
+                return Err(TinifyError::InvalidOptions {
+                    message: format!(
+                        "operation body is {} bytes, above the limit of {} bytes; check for \
+                         oversized values such as store headers",
+                        json.len(),
+                        self.max_operation_body_size
+                    ),
+                });
+            }
+        }
+
+        if self.dry_run {
+            return self.dry_run_response(url);
+        }
+
+        // Hashed once up front rather than on every retry
+        let content_md5 = body.as_ref().filter(|_| self.content_md5).map(|body| {
+            base64::engine::general_purpose::STANDARD.encode(Md5::digest(body.bytes()))
+        });
+
+        let response = self
+            .execute_request(|| {
+                let mut request = self.http_client.post(url);
+                if let Some(timeout) = self.request_timeout(None) {
+                    request = request.timeout(timeout);
+                }
+                let mut request = self.add_common_headers(request, request_id.as_deref());
+
+                if let Some(ref body) = body {
+                    if let Some(content_type) = body.content_type() {
+                        request = request.header("Content-Type", content_type);
+                    }
+                    if let Some(ref digest) = content_md5 {
+                        request = request.header("Content-MD5", digest);
+                    }
+                    self.stats.add_bytes_up(body.bytes().len());
+                    request = request.body(body.bytes().to_vec());
+                }
+
+                async move {
+                    let response = self
+                        .send(request)
+                        .await
+                        .map_err(TinifyError::ConnectionError)?;
+                    Self::handle_error_response(response).await
+                }
+            })
+            .await?;
+
+        Ok(self.annotate_response(response, request_id))
+    }
+
+    pub async fn post_stream<S: AsRef<str> + std::fmt::Debug, R>(
+        &self,
         url: S,
         stream: R,
         content_type: &str,
@@ -273,46 +1238,266 @@ impl Client {
     where
         R: AsyncRead + Send + Sync + 'static,
     {
-        let url = url.as_ref();
+        self.send_stream(url.as_ref(), stream, content_type, None)
+            .await
+    }
+
+    /// Like [`Client::post_stream`], but sends `Content-Length: len` instead of using
+    /// chunked transfer encoding
+    ///
+    /// At most `len` bytes are read from `stream`; if it ends early the request fails.
+    pub async fn post_stream_with_len<S: AsRef<str> + std::fmt::Debug, R>(
+        &self,
+        url: S,
+        stream: R,
+        len: u64,
+        content_type: &str,
+    ) -> Result<Response>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        self.send_stream(url.as_ref(), stream, content_type, Some(len))
+            .await
+    }
+
+    /// Request body that uploads `stream` chunk by chunk as it is read
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn stream_body<R>(&self, stream: R) -> Result<reqwest::Body>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        let stats = Arc::clone(&self.stats);
+        let reader_stream =
+            ReaderStream::with_capacity(stream, self.stream_chunk_size).inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    stats.add_bytes_up(chunk.len());
+                }
+            });
+        Ok(reqwest::Body::wrap_stream(reader_stream))
+    }
+
+    /// Request body holding all of `stream`, as fetch cannot stream request bodies
+    #[cfg(target_arch = "wasm32")]
+    async fn stream_body<R>(&self, stream: R) -> Result<reqwest::Body>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        let mut buffer = Vec::new();
+        Box::pin(stream).read_to_end(&mut buffer).await?;
+        self.stats.add_bytes_up(buffer.len());
+        Ok(reqwest::Body::from(buffer))
+    }
+
+    #[instrument(skip(self, stream), fields(version = VERSION, request_id = tracing::field::Empty))]
+    async fn send_stream<R>(
+        &self,
+        url: &str,
+        stream: R,
+        content_type: &str,
+        len: Option<u64>,
+    ) -> Result<Response>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
         info!("Making POST stream request to: {}", url);
+        let request_id = self.next_request_id();
+
+        if self.dry_run {
+            return self.dry_run_response(url);
+        }
 
-        let reader_stream = ReaderStream::new(stream);
-        let stream_body = reqwest::Body::wrap_stream(reader_stream);
+        let stream_body = self
+            .stream_body(stream.take(len.unwrap_or(u64::MAX)))
+            .await?;
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
 
-        let request = self.http_client.post(url);
+        let mut request = self.http_client.post(url);
+        if let Some(timeout) = self.request_timeout(None) {
+            request = request.timeout(timeout);
+        }
+        if let Some(len) = len {
+            request = request.header("Content-Length", len);
+        }
         let request = self
-            .add_common_headers(request)
+            .add_common_headers(request, request_id.as_deref())
             .header("Content-Type", content_type)
             .body(stream_body);
 
-        let response = request.send().await.map_err(TinifyError::ConnectionError)?;
-        Self::handle_error_response(response).await
+        let response = self
+            .within_deadline(async {
+                let response = self
+                    .send(request)
+                    .await
+                    .map_err(TinifyError::ConnectionError)?;
+                Self::handle_error_response(response).await
+            })
+            .await?;
+        Ok(self.annotate_response(response, request_id))
     }
 
-    #[instrument(skip(self))]
-    pub async fn get<S: AsRef<str> + std::fmt::Debug>(&self, url: S) -> Result<Response> {
-        let url = url.as_ref();
-        info!("Making GET request to: {}", url);
+    /// Send a minimal authenticated request and return the round-trip latency
+    ///
+    /// Posts an empty body to the shrink endpoint, which a valid key answers with
+    /// `400 InputMissing` without using a compression; any other answer, including a
+    /// `400` from a gateway or proxy, is reported as an error. The request isn't
+    /// retried, `timeout` overrides the client-wide timeout, and the deadline of the
+    /// [`RequestOptions`] still applies.
+    #[instrument(skip(self), fields(version = VERSION))]
+    pub async fn ping(&self, timeout: Duration) -> Result<Duration> {
+        if self.dry_run {
+            debug!("Dry run: skipping ping");
+            return Ok(Duration::ZERO);
+        }
 
-        self.execute_request(|| {
-            let request = self.http_client.get(url);
-            let request = self.add_common_headers(request);
+        self.within_deadline(async {
+            self.check_rate_limit().await?;
+            self.stats.requests.fetch_add(1, Ordering::Relaxed);
 
-            async move {
-                let response = request.send().await.map_err(TinifyError::ConnectionError)?;
-                Self::handle_error_response(response).await
+            // Capped by the deadline, if any, so a slow answer can't outlive it
+            let timeout = self.request_timeout(Some(timeout)).unwrap_or(timeout);
+            let request = self
+                .add_common_headers(self.http_client.post(self.shrink_endpoint()), None)
+                .timeout(timeout);
+
+            let started = Instant::now();
+            let response = self.send(request).await.map_err(|err| {
+                if err.is_timeout() {
+                    TinifyError::Timeout { elapsed: timeout }
+                } else {
+                    TinifyError::ConnectionError(err)
+                }
+            })?;
+            let latency = started.elapsed();
+
+            match response.status().as_u16() {
+                400 => {
+                    // Only the API's own answer to an empty upload proves the key works
+                    let status = response.status();
+                    let body_text = response.text().await.unwrap_or_default();
+                    let error_type = serde_json::from_str::<serde_json::Value>(&body_text)
+                        .ok()
+                        .and_then(|body| body.get("error")?.as_str().map(String::from));
+                    if error_type.as_deref() != Some("InputMissing") {
+                        return Err(Self::error_from_body(status, None, &body_text));
+                    }
+                }
+                401 => return Err(TinifyError::InvalidApiKey),
+                _ => {
+                    Self::handle_error_response(response).await?;
+                }
             }
+
+            debug!("Ping answered in {:?}", latency);
+            Ok(latency)
         })
         .await
     }
+
+    pub async fn get<S: AsRef<str> + std::fmt::Debug>(&self, url: S) -> Result<Response> {
+        self.fetch(reqwest::Method::GET, url.as_ref(), None, &[])
+            .await
+    }
+
+    /// GET `url` with an `Accept` header, letting the server pick among the listed types
+    pub async fn get_accepting<S: AsRef<str> + std::fmt::Debug>(
+        &self,
+        url: S,
+        accept: &str,
+    ) -> Result<Response> {
+        self.fetch(
+            reqwest::Method::GET,
+            url.as_ref(),
+            None,
+            &[("Accept", accept)],
+        )
+        .await
+    }
+
+    /// Request only the headers of `url`, without downloading the body
+    pub async fn head<S: AsRef<str> + std::fmt::Debug>(&self, url: S) -> Result<Response> {
+        self.fetch(reqwest::Method::HEAD, url.as_ref(), None, &[])
+            .await
+    }
+
+    /// Fetch `url` with a body-less `method`, overriding the client-wide timeout when
+    /// `timeout` is set and sending `headers` in addition to the common ones
+    #[instrument(skip(self), fields(version = VERSION, request_id = tracing::field::Empty))]
+    async fn fetch(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        timeout: Option<Duration>,
+        headers: &[(&str, &str)],
+    ) -> Result<Response> {
+        info!("Making {} request to: {}", method, url);
+        let request_id = self.next_request_id();
+
+        if self.dry_run {
+            return self.dry_run_response(url);
+        }
+
+        let response = self
+            .execute_request(|| {
+                let mut request = self.http_client.request(method.clone(), url);
+                if let Some(timeout) = self.request_timeout(timeout) {
+                    request = request.timeout(timeout);
+                }
+                let mut request = self.add_common_headers(request, request_id.as_deref());
+                for (name, value) in headers {
+                    request = request.header(*name, *value);
+                }
+
+                async move {
+                    let response = self
+                        .send(request)
+                        .await
+                        .map_err(TinifyError::ConnectionError)?;
+                    Self::handle_error_response(response).await
+                }
+            })
+            .await?;
+
+        Ok(self.annotate_response(response, request_id))
+    }
 }
 
 pub struct ClientBuilder {
     api_key: Option<String>,
     app_identifier: Option<String>,
+    base_url: String,
+    default_headers: Vec<(String, String)>,
+    send_request_id: bool,
+    content_md5: bool,
+    resume_downloads: bool,
+    rate_limiter_registry: Option<RateLimiterRegistry>,
+    dry_run: bool,
+    strict: bool,
     timeout: Duration,
+    download_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_max_idle_per_host: Option<usize>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_idle_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    root_certificates: Vec<Certificate>,
+    #[cfg(not(target_arch = "wasm32"))]
+    accept_invalid_certs: bool,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    decompress: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    local_address: Option<IpAddr>,
+    #[cfg(not(target_arch = "wasm32"))]
+    ip_version: Option<IpVersion>,
+    max_dimension: u32,
+    stream_chunk_size: usize,
+    max_operation_body_size: usize,
     retry_config: RetryConfig,
+    sleeper: Arc<dyn Sleeper>,
+    monthly_compression_limit: Option<u32>,
+    quota_threshold: Option<(f64, Box<QuotaCallback>)>,
     rate_limit: RateLimit,
+    #[cfg(feature = "record")]
+    record_sink: Option<Arc<dyn RecordSink>>,
 }
 
 impl ClientBuilder {
@@ -320,9 +1505,39 @@ impl ClientBuilder {
         Self {
             api_key: None,
             app_identifier: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            default_headers: Vec::new(),
+            send_request_id: false,
+            content_md5: false,
+            resume_downloads: false,
+            rate_limiter_registry: None,
+            dry_run: false,
+            strict: false,
             timeout: DEFAULT_TIMEOUT,
+            download_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_max_idle_per_host: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_idle_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            root_certificates: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            accept_invalid_certs: false,
+            decompress: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            local_address: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            ip_version: None,
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            stream_chunk_size: DEFAULT_STREAM_CHUNK_SIZE,
+            max_operation_body_size: DEFAULT_MAX_OPERATION_BODY_SIZE,
             retry_config: RetryConfig::default(),
+            sleeper: Arc::new(TokioSleeper),
+            monthly_compression_limit: None,
+            quota_threshold: None,
             rate_limit: RateLimit::default(),
+            #[cfg(feature = "record")]
+            record_sink: None,
         }
     }
 
@@ -336,11 +1551,117 @@ impl ClientBuilder {
         self
     }
 
+    /// Send requests to `url` instead of `https://api.tinify.com`
+    ///
+    /// The shrink endpoint becomes `{url}/shrink`; a trailing slash is ignored.
+    /// Output locations returned by the server are used as given.
+    pub fn base_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn default_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn send_request_id(mut self, enabled: bool) -> Self {
+        self.send_request_id = enabled;
+        self
+    }
+
+    pub fn content_md5(mut self, enabled: bool) -> Self {
+        self.content_md5 = enabled;
+        self
+    }
+
+    #[cfg(feature = "record")]
+    pub fn record_sink<S: RecordSink + 'static>(mut self, sink: S) -> Self {
+        self.record_sink = Some(Arc::new(sink));
+        self
+    }
+
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    pub fn resume_downloads(mut self, enabled: bool) -> Self {
+        self.resume_downloads = enabled;
+        self
+    }
+
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    pub fn download_timeout(mut self, timeout: Duration) -> Self {
+        self.download_timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    pub fn decompress(mut self, enabled: bool) -> Self {
+        self.decompress = enabled;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn local_address(mut self, address: IpAddr) -> Self {
+        self.local_address = Some(address);
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ip_version(mut self, version: IpVersion) -> Self {
+        self.ip_version = Some(version);
+        self
+    }
+
+    pub fn max_dimension(mut self, max: u32) -> Self {
+        self.max_dimension = max;
+        self
+    }
+
+    pub fn stream_chunk_size(mut self, size: usize) -> Self {
+        self.stream_chunk_size = size;
+        self
+    }
+
+    pub fn max_operation_body_size(mut self, size: usize) -> Self {
+        self.max_operation_body_size = size;
+        self
+    }
+
     pub fn retry_config(mut self, config: RetryConfig) -> Self {
         self.retry_config = config;
         self
@@ -351,6 +1672,24 @@ impl ClientBuilder {
         self
     }
 
+    pub fn sleeper<S: Sleeper + 'static>(mut self, sleeper: S) -> Self {
+        self.sleeper = Arc::new(sleeper);
+        self
+    }
+
+    pub fn monthly_compression_limit(mut self, limit: u32) -> Self {
+        self.monthly_compression_limit = Some(limit);
+        self
+    }
+
+    pub fn on_quota_threshold<F>(mut self, fraction: f64, callback: F) -> Self
+    where
+        F: Fn(u32, u32) + Send + Sync + 'static,
+    {
+        self.quota_threshold = Some((fraction, Box::new(callback)));
+        self
+    }
+
     pub fn max_retry_attempts(mut self, attempts: u32) -> Self {
         self.retry_config.max_attempts = attempts;
         self
@@ -361,17 +1700,81 @@ impl ClientBuilder {
         self
     }
 
-    pub fn build(self) -> Result<Client> {
-        let api_key = self.api_key.ok_or(TinifyError::InvalidApiKey)?;
-        let http_client = Client::create_http_client(self.timeout)?;
-        let rate_limiter = Client::create_rate_limiter(&self.rate_limit);
+    pub fn burst_capacity(mut self, burst: u32) -> Self {
+        self.rate_limit.burst_capacity = burst;
+        self
+    }
+
+    pub fn rate_limiter_registry(mut self, registry: &RateLimiterRegistry) -> Self {
+        self.rate_limiter_registry = Some(registry.clone());
+        self
+    }
+
+    pub fn build(mut self) -> Result<Client> {
+        let api_key = self.api_key.clone().ok_or(TinifyError::InvalidApiKey)?;
+        if PLACEHOLDER_API_KEYS.contains(&api_key.trim()) {
+            let message = "The API key is a placeholder from the examples or docs; get your own \
+                           key at https://tinypng.com/developers instead of sharing a demo key";
+            if self.strict {
+                return Err(TinifyError::InvalidOptions {
+                    message: message.to_string(),
+                });
+            }
+            warn!("{}", message);
+        }
+        self.retry_config.validate()?;
+        let quota_threshold = match self.quota_threshold.take() {
+            Some((fraction, _)) if !(fraction.is_finite() && fraction > 0.0) => {
+                return Err(TinifyError::InvalidOptions {
+                    message: format!("quota threshold must be above 0, got {}", fraction),
+                });
+            }
+            Some((fraction, callback)) => Some(Arc::new(QuotaThreshold {
+                fraction,
+                limit: self.monthly_compression_limit,
+                callback,
+                fired: AtomicBool::new(false),
+            })),
+            None => None,
+        };
+        if self.stream_chunk_size == 0 {
+            return Err(TinifyError::InvalidOptions {
+                message: "stream_chunk_size must be at least 1 byte".to_string(),
+            });
+        }
+        if let Some(identifier) = &self.app_identifier {
+            Client::validate_app_identifier(identifier)?;
+        }
+        let http_client = Client::create_http_client(&self)?;
+        let rate_limiter = match &self.rate_limiter_registry {
+            Some(registry) => registry.limiter_for(&api_key, &self.rate_limit),
+            None => Client::create_rate_limiter(&self.rate_limit),
+        };
+        let default_headers = Client::create_default_headers(&self.default_headers)?;
 
         Ok(Client {
             http_client,
             api_key,
             app_identifier: self.app_identifier,
+            base_url: self.base_url,
+            default_headers,
+            send_request_id: self.send_request_id,
+            content_md5: self.content_md5,
+            resume_downloads: self.resume_downloads,
+            dry_run: self.dry_run,
+            strict: self.strict,
+            download_timeout: self.download_timeout,
+            max_dimension: self.max_dimension,
+            stream_chunk_size: self.stream_chunk_size,
+            max_operation_body_size: self.max_operation_body_size,
             retry_config: self.retry_config,
+            sleeper: self.sleeper,
+            quota_threshold,
+            request_options: RequestOptions::default(),
             rate_limiter,
+            stats: Arc::default(),
+            #[cfg(feature = "record")]
+            recorder: self.record_sink.map(Recorder::new),
         })
     }
 }
@@ -381,3 +1784,1250 @@ impl Default for ClientBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_limit_charges_one_permit_per_request() {
+        // 1200 rpm = one permit every 50ms, no burst beyond a single request
+        let client = Client::builder()
+            .api_key("test-key")
+            .rate_limit(RateLimit {
+                requests_per_minute: 1200,
+                burst_capacity: 1,
+            })
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            client.check_rate_limit().await.unwrap();
+        }
+        let elapsed = start.elapsed();
+
+        // The first permit is immediate, the remaining four are spaced 50ms apart
+        assert!(elapsed >= Duration::from_millis(190), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(400), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_at_total_duration_budget() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/output/abc")
+            .with_status(503)
+            .with_body(r#"{"error":"ServiceUnavailable","message":"Try again"}"#)
+            .expect_at_most(3)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .retry_config(RetryConfig {
+                max_attempts: 20,
+                base_delay: Duration::from_millis(40),
+                max_delay: Duration::from_millis(40),
+                backoff_factor: 1.0,
+                max_total_duration: Some(Duration::from_millis(100)),
+            })
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let result = client.get(format!("{}/output/abc", server.url())).await;
+
+        assert!(matches!(
+            result,
+            Err(TinifyError::RetriesExhausted { source, .. })
+                if matches!(*source, TinifyError::ServerError { .. })
+        ));
+        assert!(start.elapsed() < Duration::from_millis(500));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted_reports_attempts() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/output/abc")
+            .with_status(502)
+            .with_body(r#"{"error":"BadGateway","message":"Upstream down"}"#)
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .retry_config(RetryConfig {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(20),
+                max_delay: Duration::from_millis(20),
+                backoff_factor: 1.0,
+                max_total_duration: None,
+            })
+            .build()
+            .unwrap();
+
+        let err = client
+            .get(format!("{}/output/abc", server.url()))
+            .await
+            .unwrap_err();
+        match &err {
+            TinifyError::RetriesExhausted {
+                attempts,
+                elapsed,
+                source,
+            } => {
+                assert_eq!(*attempts, 3);
+                assert!(*elapsed >= Duration::from_millis(40), "{elapsed:?}");
+                assert!(matches!(
+                    **source,
+                    TinifyError::ServerError {
+                        status: Some(502),
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected RetriesExhausted, got {other:?}"),
+        }
+        assert_eq!(err.status_code(), Some(502));
+        assert!(!err.is_retryable());
+        mock.assert_async().await;
+    }
+
+    /// Records requested delays instead of waiting
+    #[derive(Debug, Default, Clone)]
+    struct FakeSleeper(Arc<std::sync::Mutex<Vec<Duration>>>);
+
+    impl Sleeper for FakeSleeper {
+        fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+            self.0.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backoff_with_fake_sleeper() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/output/abc")
+            .with_status(503)
+            .expect(5)
+            .create_async()
+            .await;
+
+        let sleeper = FakeSleeper::default();
+        let client = Client::builder()
+            .api_key("test-key")
+            .retry_config(RetryConfig {
+                max_attempts: 5,
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(5),
+                backoff_factor: 2.0,
+                max_total_duration: None,
+            })
+            .sleeper(sleeper.clone())
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let result = client.get(format!("{}/output/abc", server.url())).await;
+        assert!(matches!(
+            result,
+            Err(TinifyError::RetriesExhausted { attempts: 5, .. })
+        ));
+        // 1 + 2 + 4 + 5 seconds of backoff were requested but none were waited out
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(
+            *sleeper.0.lock().unwrap(),
+            [1, 2, 4, 5].map(Duration::from_secs)
+        );
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_quota_threshold_fires_once() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for count in [100, 450, 480] {
+            mocks.push(
+                server
+                    .mock("GET", format!("/output/{count}").as_str())
+                    .with_status(200)
+                    .with_header("Compression-Count", &count.to_string())
+                    .with_body("ok")
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let fired = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = Client::builder()
+            .api_key("test-key")
+            .monthly_compression_limit(500)
+            .on_quota_threshold(0.8, {
+                let fired = Arc::clone(&fired);
+                move |count, limit| fired.lock().unwrap().push((count, limit))
+            })
+            .build()
+            .unwrap();
+
+        for count in [100, 450, 480] {
+            client
+                .get(format!("{}/output/{count}", server.url()))
+                .await
+                .unwrap();
+        }
+        assert_eq!(*fired.lock().unwrap(), [(450, 500)]);
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+
+        let invalid = Client::builder()
+            .api_key("test-key")
+            .on_quota_threshold(0.0, |_, _| {})
+            .build();
+        assert!(matches!(invalid, Err(TinifyError::InvalidOptions { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_makes_a_single_attempt() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/shrink")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .max_retry_attempts(5)
+            .build()
+            .unwrap()
+            .with_request_options(RequestOptions::new().no_retry());
+
+        let result = client
+            .post(client.shrink_endpoint(), Some(RequestBody::image(vec![1])))
+            .await;
+        assert!(matches!(result, Err(TinifyError::ServerError { .. })));
+        assert_eq!(client.stats().retries, 0);
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_retry_config_is_validated() {
+        let build = |config: RetryConfig| {
+            Client::builder()
+                .api_key("test-key")
+                .retry_config(config)
+                .build()
+        };
+
+        let inverted = RetryConfig {
+            base_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(1),
+            ..RetryConfig::default()
+        };
+        assert!(matches!(
+            build(inverted),
+            Err(TinifyError::InvalidOptions { message }) if message.contains("max_delay")
+        ));
+
+        let shrinking = RetryConfig {
+            backoff_factor: 0.5,
+            ..RetryConfig::default()
+        };
+        assert!(matches!(
+            build(shrinking),
+            Err(TinifyError::InvalidOptions { message }) if message.contains("backoff_factor")
+        ));
+
+        assert!(RetryConfig {
+            max_attempts: 0,
+            ..RetryConfig::default()
+        }
+        .validate()
+        .is_err());
+        assert!(build(RetryConfig::default()).is_ok());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_retry_events_have_structured_fields() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/output/abc")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .retry_config(RetryConfig {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(10),
+                ..RetryConfig::default()
+            })
+            .build()
+            .unwrap();
+
+        let result = client.get(format!("{}/output/abc", server.url())).await;
+        assert!(matches!(result, Err(TinifyError::RetriesExhausted { .. })));
+
+        assert!(logs_contain("attempt{attempt=1 max_attempts=2}"));
+        assert!(logs_contain("attempt{attempt=2 max_attempts=2}"));
+        assert!(logs_contain(
+            "attempt=1 max_attempts=2 delay_ms=10 error_kind=\"ServerError\""
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_described() {
+        let client = Client::builder()
+            .api_key("test-key")
+            .max_retry_attempts(1)
+            .build()
+            .unwrap();
+
+        // Nothing listens on port 1, so the connection is refused immediately
+        let err = client.get("http://127.0.0.1:1/output").await.unwrap_err();
+
+        assert!(matches!(err, TinifyError::ConnectionError(_)));
+        assert!(err.is_retryable());
+        assert!(err.to_string().starts_with("Failed to connect"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_ping_reports_latency_and_maps_failures() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/shrink")
+            .match_header(
+                "authorization",
+                mockito::Matcher::Regex("^Basic ".to_string()),
+            )
+            .with_status(400)
+            .with_body(r#"{"error":"InputMissing","message":"Input file is empty"}"#)
+            .create_async()
+            .await;
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let started = Instant::now();
+        let latency = client.ping(Duration::from_secs(5)).await.unwrap();
+        assert!(latency > Duration::ZERO);
+        assert!(latency <= started.elapsed());
+        assert_eq!(client.stats().requests, 1);
+
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/shrink")
+            .with_status(401)
+            .with_body(r#"{"error":"Unauthorized","message":"Credentials are invalid"}"#)
+            .create_async()
+            .await;
+        let client = Client::builder()
+            .api_key("bad-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.ping(Duration::from_secs(5)).await,
+            Err(TinifyError::InvalidApiKey)
+        ));
+
+        // A gateway rejecting the request is not a healthy API
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/shrink")
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Malformed request header"}"#)
+            .create_async()
+            .await;
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.ping(Duration::from_secs(5)).await,
+            Err(TinifyError::ClientError { status: Some(400), error_type: Some(error_type), .. })
+                if error_type == "BadRequest"
+        ));
+
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(format!("http://{}", addr))
+            .build()
+            .unwrap();
+        let timeout = Duration::from_millis(100);
+        assert!(matches!(
+            client.ping(timeout).await,
+            Err(TinifyError::Timeout { elapsed }) if elapsed == timeout
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_gzip_encoded_download_is_decoded() {
+        use std::io::Write;
+
+        let image = b"\x89PNG\r\n\x1a\ncompressed image bytes".to_vec();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&image).unwrap();
+        let encoded = encoder.finish().unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_header("Content-Encoding", "gzip")
+            .with_body(encoded.clone())
+            .create_async()
+            .await;
+        let url = format!("{}/output/abc", server.url());
+
+        let client = Client::new("test-key".to_string()).unwrap();
+        assert_eq!(client.download(&url).await.unwrap(), image);
+
+        let raw = Client::builder()
+            .api_key("test-key")
+            .decompress(false)
+            .build()
+            .unwrap();
+        assert_eq!(raw.download(&url).await.unwrap(), encoded);
+    }
+
+    #[tokio::test]
+    async fn test_ip_version_restricts_resolved_addresses() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("ok")
+            .create_async()
+            .await;
+        // The mock server only listens on IPv4
+        let url = format!(
+            "http://localhost:{}/output/abc",
+            server.socket_address().port()
+        );
+
+        let v4 = Client::builder()
+            .api_key("test-key")
+            .ip_version(IpVersion::V4)
+            .build()
+            .unwrap();
+        assert_eq!(v4.download(&url).await.unwrap(), b"ok");
+
+        let v6 = Client::builder()
+            .api_key("test-key")
+            .ip_version(IpVersion::V6)
+            .max_retry_attempts(1)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            v6.download(&url).await,
+            Err(TinifyError::ConnectionError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_default_headers_are_sent() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/output/abc")
+            .match_header("X-Correlation-Id", "abc-123")
+            .match_header("Authorization", "Basic YXBpOnRlc3Qta2V5")
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .default_header("X-Correlation-Id", "abc-123")
+            .build()
+            .unwrap();
+        client
+            .get(format!("{}/output/abc", server.url()))
+            .await
+            .unwrap();
+        mock.assert_async().await;
+
+        let result = Client::builder()
+            .api_key("test-key")
+            .default_header("authorization", "Bearer other")
+            .build();
+        assert!(matches!(result, Err(TinifyError::InvalidOptions { .. })));
+    }
+
+    #[test]
+    fn test_default_headers_cannot_collide_with_managed_headers() {
+        for name in [
+            "User-Agent",
+            "content-type",
+            "Content-Length",
+            "Range",
+            "X-Request-Id",
+            "Content-MD5",
+            "Accept-Encoding",
+        ] {
+            let result = Client::builder()
+                .api_key("test-key")
+                .app_identifier("MyApp/1.0")
+                .default_header(name, "value")
+                .build();
+            assert!(
+                matches!(
+                    &result,
+                    Err(TinifyError::InvalidOptions { message }) if message.contains("set by the client")
+                ),
+                "{name}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_header() {
+        let mut server = mockito::Server::new_async().await;
+        let with_id = server
+            .mock("GET", "/output/abc")
+            .match_header(
+                "X-Request-Id",
+                mockito::Matcher::Regex("^[0-9a-f-]{36}$".into()),
+            )
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .send_request_id(true)
+            .build()
+            .unwrap();
+        let response = client
+            .get(format!("{}/output/abc", server.url()))
+            .await
+            .unwrap();
+        assert!(response.extensions().get::<RequestId>().is_some());
+        with_id.assert_async().await;
+
+        let without_id = server
+            .mock("GET", "/output/def")
+            .match_header("X-Request-Id", mockito::Matcher::Missing)
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Client::new("test-key".to_string()).unwrap();
+        let response = client
+            .get(format!("{}/output/def", server.url()))
+            .await
+            .unwrap();
+        assert!(response.extensions().get::<RequestId>().is_none());
+        without_id.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_content_md5_header() {
+        let mut server = mockito::Server::new_async().await;
+        let with_md5 = server
+            .mock("POST", "/shrink")
+            .match_header("Content-MD5", "HtFgfiPGAubA6HoBdGsRvw==")
+            .match_body("image-data")
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .content_md5(true)
+            .build()
+            .unwrap();
+        client
+            .post(
+                client.shrink_endpoint(),
+                Some(RequestBody::image(b"image-data".to_vec())),
+            )
+            .await
+            .unwrap();
+        with_md5.assert_async().await;
+
+        let json_md5 = server
+            .mock("POST", "/output/abc")
+            .match_header("Content-MD5", "mZFLkyvTelC5g8XnyQrpOw==")
+            .match_body("{}")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        client
+            .post(
+                format!("{}/output/abc", server.url()),
+                Some(RequestBody::json(&serde_json::json!({})).unwrap()),
+            )
+            .await
+            .unwrap();
+        json_md5.assert_async().await;
+
+        let without_md5 = server
+            .mock("POST", "/shrink")
+            .match_header("Content-MD5", mockito::Matcher::Missing)
+            .match_body("image-data")
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        client
+            .post(
+                client.shrink_endpoint(),
+                Some(RequestBody::image(b"image-data".to_vec())),
+            )
+            .await
+            .unwrap();
+        without_md5.assert_async().await;
+    }
+
+    #[cfg(feature = "record")]
+    #[tokio::test]
+    async fn test_record_sink_redacts_api_key() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", "https://api.tinify.com/output/abc")
+            .create_async()
+            .await;
+
+        let recorded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&recorded);
+        let client = Client::builder()
+            .api_key("secret-api-key")
+            .base_url(server.url())
+            .record_sink(move |exchange: &crate::RecordedExchange| {
+                sink.lock().unwrap().push(exchange.clone());
+            })
+            .build()
+            .unwrap();
+        client
+            .post(
+                client.shrink_endpoint(),
+                Some(RequestBody::image(b"image".to_vec())),
+            )
+            .await
+            .unwrap();
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        let exchange = &recorded[0];
+        assert_eq!(exchange.method, "POST");
+        assert_eq!(exchange.url, format!("{}/shrink", server.url()));
+        assert_eq!(exchange.status, Some(201));
+        assert_eq!(
+            exchange
+                .response_headers
+                .get("location")
+                .map(String::as_str),
+            Some("https://api.tinify.com/output/abc")
+        );
+        assert_eq!(
+            exchange
+                .request_headers
+                .get("authorization")
+                .map(String::as_str),
+            Some("[redacted]")
+        );
+
+        let json = serde_json::to_string(exchange).unwrap();
+        let encoded_key = base64::engine::general_purpose::STANDARD.encode("api:secret-api-key");
+        assert!(!json.contains("secret-api-key"));
+        assert!(!json.contains(&encoded_key));
+    }
+
+    /// Reader that records the largest buffer it was asked to fill
+    struct ReadSizeProbe {
+        inner: std::io::Cursor<Vec<u8>>,
+        max_read: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl AsyncRead for ReadSizeProbe {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            self.max_read.fetch_max(buf.remaining(), Ordering::Relaxed);
+            std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_upload_uses_chunk_size() {
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/shrink")
+            .match_body(data.clone())
+            .with_status(201)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let chunk_size = 256 * 1024;
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .stream_chunk_size(chunk_size)
+            .build()
+            .unwrap();
+        let max_read = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let reader = ReadSizeProbe {
+            inner: std::io::Cursor::new(data.clone()),
+            max_read: Arc::clone(&max_read),
+        };
+
+        client
+            .post_stream(client.shrink_endpoint(), reader, "image/png")
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        assert!(max_read.load(Ordering::Relaxed) >= chunk_size);
+        assert_eq!(client.stats().bytes_up, data.len() as u64);
+
+        assert!(matches!(
+            Client::builder()
+                .api_key("test-key")
+                .stream_chunk_size(0)
+                .build(),
+            Err(TinifyError::InvalidOptions { .. })
+        ));
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_placeholder_api_key_warns() {
+        Client::builder()
+            .api_key("XZmVxmxJxbx4PZbHyxwX74v8N0LLtvqq")
+            .build()
+            .unwrap();
+        assert!(logs_contain("The API key is a placeholder"));
+    }
+
+    #[test]
+    fn test_app_identifier_must_be_a_header_value() {
+        let client = Client::builder()
+            .api_key("test-key")
+            .app_identifier("MyApp/1.0 (+https://example.com)")
+            .build();
+        assert!(client.is_ok());
+
+        let result = Client::builder()
+            .api_key("test-key")
+            .app_identifier("MyApp/1.0\r\nX-Injected: yes")
+            .build();
+        assert!(matches!(
+            result,
+            Err(TinifyError::InvalidOptions { message }) if message.contains("app identifier")
+        ));
+
+        let mut client = client.unwrap();
+        assert!(client.set_app_identifier("MyApp\n".to_string()).is_err());
+        assert!(client.set_app_identifier("MyApp/2.0".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_strict_rejects_placeholder_api_key() {
+        let result = Client::builder()
+            .api_key("your-api-key")
+            .strict(true)
+            .build();
+        assert!(matches!(
+            result,
+            Err(TinifyError::InvalidOptions { message }) if message.contains("placeholder")
+        ));
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_real_api_key_does_not_warn() {
+        Client::builder().api_key("my-own-key").build().unwrap();
+        assert!(!logs_contain("The API key is a placeholder"));
+    }
+
+    /// Start offset of a `Range: bytes=N-` request header
+    fn range_offset(request: &mockito::Request) -> usize {
+        let range = request.header("Range")[0].to_str().unwrap();
+        range["bytes=".len()..range.len() - 1].parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_with_range() {
+        let mut server = mockito::Server::new_async().await;
+        let interrupted = server
+            .mock("GET", "/output/abc")
+            .match_header("Range", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("Accept-Ranges", "bytes")
+            .with_chunked_body(|w| {
+                w.write_all(b"hello")?;
+                w.flush()?;
+                // Give the headers and first bytes time to go out before dropping
+                std::thread::sleep(Duration::from_millis(100));
+                Err(std::io::Error::other("connection dropped"))
+            })
+            .create_async()
+            .await;
+        let remainder = server
+            .mock("GET", "/output/abc")
+            .match_header("Range", mockito::Matcher::Regex(r"^bytes=\d+-$".into()))
+            .with_status(206)
+            .with_header_from_request("Content-Range", |request| {
+                let offset = range_offset(request);
+                format!("bytes {}-10/11", offset)
+            })
+            .with_body_from_request(|request| {
+                // Serve whatever the client has not received yet
+                b"hello world"[range_offset(request)..].to_vec()
+            })
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .resume_downloads(true)
+            .build()
+            .unwrap();
+        let bytes = client
+            .download(format!("{}/output/abc", server.url()))
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, b"hello world");
+        interrupted.assert_async().await;
+        remainder.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_resumable_download_is_not_content_encoded() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello world").unwrap();
+        let encoded = encoder.finish().unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        // What a compressing proxy would send if the client advertised gzip
+        let compressed = server
+            .mock("GET", "/output/abc")
+            .match_header("Accept-Encoding", mockito::Matcher::Regex("gzip".into()))
+            .with_status(200)
+            .with_header("Accept-Ranges", "bytes")
+            .with_header("Content-Encoding", "gzip")
+            .with_body(encoded)
+            .expect(0)
+            .create_async()
+            .await;
+        let interrupted = server
+            .mock("GET", "/output/abc")
+            .match_header("Accept-Encoding", "identity")
+            .match_header("Range", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("Accept-Ranges", "bytes")
+            .with_chunked_body(|w| {
+                w.write_all(b"hello")?;
+                w.flush()?;
+                // Give the headers and first bytes time to go out before dropping
+                std::thread::sleep(Duration::from_millis(100));
+                Err(std::io::Error::other("connection dropped"))
+            })
+            .create_async()
+            .await;
+        let remainder = server
+            .mock("GET", "/output/abc")
+            .match_header("Accept-Encoding", "identity")
+            .match_header("Range", mockito::Matcher::Regex(r"^bytes=\d+-$".into()))
+            .with_status(206)
+            .with_header_from_request("Content-Range", |request| {
+                format!("bytes {}-10/11", range_offset(request))
+            })
+            .with_body_from_request(|request| b"hello world"[range_offset(request)..].to_vec())
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .decompress(true)
+            .resume_downloads(true)
+            .build()
+            .unwrap();
+        let bytes = client
+            .download(format!("{}/output/abc", server.url()))
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, b"hello world");
+        compressed.assert_async().await;
+        interrupted.assert_async().await;
+        remainder.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_download_rejects_mismatched_content_range() {
+        let mut server = mockito::Server::new_async().await;
+        let _interrupted = server
+            .mock("GET", "/output/abc")
+            .match_header("Range", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("Accept-Ranges", "bytes")
+            .with_chunked_body(|w| {
+                w.write_all(b"hello")?;
+                w.flush()?;
+                // Give the headers and first bytes time to go out before dropping
+                std::thread::sleep(Duration::from_millis(100));
+                Err(std::io::Error::other("connection dropped"))
+            })
+            .create_async()
+            .await;
+        // A proxy answering with a different range than the one requested
+        let _shifted = server
+            .mock("GET", "/output/abc")
+            .match_header("Range", mockito::Matcher::Regex(r"^bytes=\d+-$".into()))
+            .with_status(206)
+            .with_header_from_request("Content-Range", |request| {
+                format!("bytes {}-10/11", range_offset(request) + 1)
+            })
+            .with_body_from_request(|request| b"hello world"[range_offset(request) + 1..].to_vec())
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .resume_downloads(true)
+            .build()
+            .unwrap();
+        let result = client
+            .download(format!("{}/output/abc", server.url()))
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(TinifyError::UnknownError { message, status: Some(206) })
+                if message.contains("Content-Range")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_burst_capacity_is_honored() {
+        let client = Client::builder()
+            .api_key("test-key")
+            .requests_per_minute(60)
+            .burst_capacity(3)
+            .build()
+            .unwrap();
+        assert_eq!(client.rate_limit_available(), 3);
+
+        // The whole burst is granted without waiting for the 1/s replenish rate
+        let start = Instant::now();
+        for expected in [2, 1, 0] {
+            client.check_rate_limit().await.unwrap();
+            assert_eq!(client.rate_limit_available(), expected);
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_non_json_error_body_is_reported() {
+        let page = format!(
+            "<html><body>502 Bad Gateway{}</body></html>",
+            "!".repeat(500)
+        );
+        let response = Response::from(
+            http::Response::builder()
+                .status(502)
+                .header("Content-Type", "text/html")
+                .body(page)
+                .unwrap(),
+        );
+
+        match Client::handle_error_response(response).await {
+            Err(TinifyError::ServerError {
+                message, status, ..
+            }) => {
+                assert_eq!(status, Some(502));
+                assert!(
+                    message.starts_with("<html><body>502 Bad Gateway"),
+                    "{message}"
+                );
+                assert!(message.ends_with("... (truncated)"), "{message}");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unusual_status_codes_are_preserved() {
+        let response = |status: u16| {
+            Response::from(
+                http::Response::builder()
+                    .status(status)
+                    .body(r#"{"error":"Teapot","message":"Short and stout"}"#)
+                    .unwrap(),
+            )
+        };
+
+        let err = Client::handle_error_response(response(418))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TinifyError::ClientError { .. }));
+        assert_eq!(err.status_code(), Some(418));
+
+        let err = Client::handle_error_response(response(304))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            TinifyError::UnknownError { message, .. } if message == "Short and stout"
+        ));
+        assert_eq!(err.status_code(), Some(304));
+        assert_eq!(err.to_snapshot().status, Some(304));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_quota_exceeded_is_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(429)
+            .with_body(
+                r#"{"error":"TooManyRequests","message":"Your monthly limit has been exceeded"}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .max_retry_attempts(3)
+            .build()
+            .unwrap();
+
+        let err = client
+            .post(
+                client.shrink_endpoint(),
+                Some(RequestBody::image(vec![1, 2, 3])),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, TinifyError::QuotaExceeded { .. }));
+        assert!(!err.is_retryable());
+        assert_eq!(client.stats().retries, 0);
+        assert!(logs_contain("ERROR"));
+        assert!(logs_contain("Monthly compression quota exceeded"));
+        shrink.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_quota_vs_rate_limit_detection() {
+        let response = |body: &str| {
+            Response::from(
+                http::Response::builder()
+                    .status(429)
+                    .header("Retry-After", "7")
+                    .body(body.to_string())
+                    .unwrap(),
+            )
+        };
+
+        let result = Client::handle_error_response(response(
+            r#"{"error":"TooManyRequests","message":"Too many requests, slow down"}"#,
+        ))
+        .await;
+        match result {
+            Err(TinifyError::RateLimitExceeded {
+                retry_after,
+                message,
+                error_type,
+            }) => {
+                assert_eq!(retry_after, 7);
+                assert_eq!(message, "Too many requests, slow down");
+                assert_eq!(error_type.as_deref(), Some("TooManyRequests"));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let result = Client::handle_error_response(response(
+            r#"{"error":"TooManyRequests","message":"Your monthly limit has been exceeded"}"#,
+        ))
+        .await;
+        match result {
+            Err(TinifyError::QuotaExceeded {
+                message,
+                error_type,
+            }) => {
+                assert_eq!(message, "Your monthly limit has been exceeded");
+                assert_eq!(error_type.as_deref(), Some("TooManyRequests"));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        // Without an error type, fall back to the message
+        let result = Client::handle_error_response(response(
+            r#"{"message":"Your monthly limit has been exceeded"}"#,
+        ))
+        .await;
+        assert!(matches!(result, Err(TinifyError::QuotaExceeded { .. })));
+
+        let result = Client::handle_error_response(response("Too many requests")).await;
+        assert!(matches!(result, Err(TinifyError::RateLimitExceeded { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_registry_shares_limiter_per_api_key() {
+        let registry = RateLimiterRegistry::new();
+        let build = |key: &str| {
+            Client::builder()
+                .api_key(key)
+                .requests_per_minute(60)
+                .burst_capacity(2)
+                .rate_limiter_registry(&registry)
+                .build()
+                .unwrap()
+        };
+        let first = build("shared-key");
+        let second = build("shared-key");
+        let other = build("other-key");
+
+        first.check_rate_limit().await.unwrap();
+        second.check_rate_limit().await.unwrap();
+
+        // Both handles drew from the same two-permit burst
+        assert_eq!(first.rate_limit_available(), 0);
+        assert_eq!(second.rate_limit_available(), 0);
+        assert_eq!(other.rate_limit_available(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_download_uses_dedicated_timeout() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(300));
+                w.write_all(b"slow image")
+            })
+            .create_async()
+            .await;
+        let url = format!("{}/output/abc", server.url());
+
+        let build = |download_timeout: Option<Duration>| {
+            let mut builder = Client::builder()
+                .api_key("test-key")
+                .timeout(Duration::from_millis(100))
+                .max_retry_attempts(1);
+            if let Some(timeout) = download_timeout {
+                builder = builder.download_timeout(timeout);
+            }
+            builder.build().unwrap()
+        };
+
+        let client = build(Some(Duration::from_secs(5)));
+        assert_eq!(client.download(&url).await.unwrap(), b"slow image");
+
+        let client = build(None);
+        let err = client.download(&url).await.unwrap_err();
+        assert!(err.to_string().starts_with("Request timed out"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_post_content_type_follows_body_kind() {
+        let mut server = mockito::Server::new_async().await;
+        let json = server
+            .mock("POST", "/json")
+            .match_header("Content-Type", "application/json")
+            .match_body(r#" {"resize":{}}"#)
+            .with_status(200)
+            .create_async()
+            .await;
+        let image = server
+            .mock("POST", "/image")
+            .match_header("Content-Type", mockito::Matcher::Missing)
+            .match_body("{image bytes")
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        client
+            .post(
+                format!("{}/json", server.url()),
+                Some(RequestBody::Json(br#" {"resize":{}}"#.to_vec())),
+            )
+            .await
+            .unwrap();
+        client
+            .post(
+                format!("{}/image", server.url()),
+                Some(RequestBody::image(b"{image bytes".to_vec())),
+            )
+            .await
+            .unwrap();
+
+        json.assert_async().await;
+        image.assert_async().await;
+    }
+
+    #[test]
+    fn test_extra_root_certificate_is_accepted() {
+        const TEST_CA: &[u8] = b"-----BEGIN CERTIFICATE-----
+MIIBiTCCAS+gAwIBAgIUC/mGcT2UV9M1RbvqqRpHjwqT0IswCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOdGluaWZ5LXRlc3QtY2EwIBcNMjYxMDE2MTg1MjA5WhgPMjEy
+NjA5MjIxODUyMDlaMBkxFzAVBgNVBAMMDnRpbmlmeS10ZXN0LWNhMFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAE/1zei6LYlkBw0q+Q8mVA3rkk3GzxaFvp7eiovbGS
+XQKwQtNZu+XaKA6hBeEv/1x1Ao4VtZi7Liw4woBEgzaG6qNTMFEwHQYDVR0OBBYE
+FLHLBldR2TtJexiwo0nrlwlS43KYMB8GA1UdIwQYMBaAFLHLBldR2TtJexiwo0nr
+lwlS43KYMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgFVctSckf
+9UcuHcnLcxRMDOyQ6rl/eZuj2TEHU+vtzKICIQDT+PyEoQEr738DWp4VRUy+Kj+/
+dM3u2fHTGhQQwouzcg==
+-----END CERTIFICATE-----
+";
+        let certificate = Certificate::from_pem(TEST_CA).unwrap();
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .add_root_certificate(certificate)
+            .build();
+        assert!(client.is_ok());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::client::{RateLimit, RetryConfig};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Declarative client settings, e.g. loaded from a service's own config file
+///
+/// Every field is optional; unset fields keep the builder defaults. Durations are
+/// (de)serialized as whole milliseconds. Apply with [`TinifyBuilder::from_config`].
+///
+/// [`TinifyBuilder::from_config`]: crate::TinifyBuilder::from_config
+///
+/// # Examples
+///
+/// ```
+/// use tinify::{Tinify, TinifyConfig};
+///
+/// let config: TinifyConfig = serde_json::from_str(
+///     r#"{ "api_key": "your-api-key", "timeout": 10000, "rate_limit": { "requests_per_minute": 30 } }"#,
+/// )?;
+/// let client = Tinify::builder().from_config(config).build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TinifyConfig {
+    pub api_key: Option<String>,
+    pub app_identifier: Option<String>,
+    pub base_url: Option<String>,
+    #[serde(with = "option_duration_millis")]
+    pub timeout: Option<Duration>,
+    #[serde(with = "option_duration_millis")]
+    pub download_timeout: Option<Duration>,
+    pub retry: Option<RetryConfig>,
+    pub rate_limit: Option<RateLimit>,
+    pub max_dimension: Option<u32>,
+    pub decompress: Option<bool>,
+    pub dry_run: Option<bool>,
+    pub strict: Option<bool>,
+}
+
+/// Serde adapter storing a [`Duration`] as whole milliseconds
+pub(crate) mod duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// Like [`duration_millis`], for optional durations
+pub(crate) mod option_duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|ms| ms.map(Duration::from_millis))
+    }
+}
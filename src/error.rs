@@ -1,32 +1,60 @@
+use crate::options::ImageFormat;
+use serde::Serialize;
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
+/// Seconds to wait after a 429 that doesn't say how long to back off
+const DEFAULT_RETRY_AFTER: u64 = 60;
+
 #[derive(Error, Debug)]
 pub enum TinifyError {
     #[error("API key invalid or missing")]
     InvalidApiKey,
 
     #[error("Monthly quota exceeded")]
-    QuotaExceeded,
+    QuotaExceeded {
+        message: String,
+        error_type: Option<String>,
+    },
 
     #[error("File too large: {size} bytes (max: {max_size} bytes)")]
     FileTooLarge { size: u64, max_size: u64 },
 
+    #[error("Input is empty, there is no image data to compress")]
+    EmptyInput,
+
     #[error("Unsupported file format: {format}")]
     UnsupportedFormat { format: String },
 
+    /// The API refused to convert to `format`, which isn't available for this
+    /// account, plan or region; fall back to another format, e.g. WebP for AVIF
+    #[error("Converting to {format:?} is not available for this account or region")]
+    FormatNotAvailable { format: ImageFormat },
+
     #[error("File not found: {path}")]
     FileNotFound { path: PathBuf },
 
     #[error("Rate limit exceeded, retry after {retry_after} seconds")]
-    RateLimitExceeded { retry_after: u64 },
+    RateLimitExceeded {
+        retry_after: u64,
+        message: String,
+        error_type: Option<String>,
+    },
 
-    #[error("Invalid resize dimensions: width={width:?}, height={height:?}")]
+    #[error("Invalid resize dimensions: width={width:?}, height={height:?} (each must be 1 to {max} pixels)")]
     InvalidDimensions {
         width: Option<u32>,
         height: Option<u32>,
+        max: u32,
     },
 
+    #[error("Invalid background color: {value} (expected #RGB, #RRGGBB, \"white\" or \"black\")")]
+    InvalidBackground { value: String },
+
+    #[error("Invalid options: {message}")]
+    InvalidOptions { message: String },
+
     #[error("Client not initialized. Call TinifyClient::new() or use TinifyClientBuilder")]
     ClientNotInitialized,
 
@@ -51,7 +79,7 @@ pub enum TinifyError {
         status: Option<u16>,
     },
 
-    #[error("Connection error: {0}")]
+    #[error("{}", describe_reqwest_error(.0))]
     ConnectionError(#[from] reqwest::Error),
 
     #[error("I/O error: {0}")]
@@ -60,11 +88,342 @@ pub enum TinifyError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Base64 decode error: {0}")]
+    Base64Error(#[from] base64::DecodeError),
+
+    #[error("Invalid data URI: {message}")]
+    InvalidDataUri { message: String },
+
+    #[error("Invalid URL: {message}")]
+    InvalidUrl { message: String },
+
     #[error("URL parse error: {0}")]
     UrlParseError(#[from] url::ParseError),
 
+    #[error("Operation timed out after {elapsed:?}")]
+    Timeout { elapsed: Duration },
+
+    /// A retryable failure persisted through every allowed attempt
+    ///
+    /// `source` is the error of the last attempt; match on it for the underlying cause.
+    /// It is exposed through [`std::error::Error::source`] rather than repeated in the
+    /// message, so error-chain reporters print it once.
+    #[error("Giving up after {attempts} attempts over {elapsed:?}")]
+    RetriesExhausted {
+        attempts: u32,
+        elapsed: Duration,
+        source: Box<TinifyError>,
+    },
+
     #[error("Unknown error: {message}")]
-    UnknownError { message: String },
+    UnknownError {
+        message: String,
+        status: Option<u16>,
+    },
+}
+
+impl TinifyError {
+    /// Whether the request that produced this error may succeed if retried
+    ///
+    /// Network failures (including connection refused, DNS failures and timeouts),
+    /// server errors and rate limiting are retryable; everything else is not.
+    /// In particular [`TinifyError::QuotaExceeded`] is never retryable: the monthly
+    /// quota won't come back until the next billing period or a plan upgrade, and
+    /// [`TinifyError::RetriesExhausted`] is not either, since retrying already failed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            TinifyError::ConnectionError(_)
+                | TinifyError::ServerError { .. }
+                | TinifyError::RateLimitExceeded { .. }
+        )
+    }
+
+    /// Classify an error response from the API by its status code
+    ///
+    /// * 401 with a message mentioning credentials is [`TinifyError::InvalidApiKey`],
+    ///   any other 401 an [`TinifyError::AccountError`]
+    /// * 429 is [`TinifyError::QuotaExceeded`] or [`TinifyError::RateLimitExceeded`],
+    ///   see below
+    /// * other 4xx are [`TinifyError::ClientError`], 5xx [`TinifyError::ServerError`]
+    /// * anything else is an [`TinifyError::UnknownError`] carrying the status
+    ///
+    /// A 429 whose message mentions the monthly limit or quota is an exhausted quota,
+    /// whatever its `error_type`: Tinify reports both rate limiting and an exhausted
+    /// quota as `TooManyRequests`. Otherwise `TooManyRequests` or a missing type is
+    /// rate limiting and any other type is treated as an exhausted quota, so an
+    /// exhausted account isn't retried.
+    /// `RateLimitExceeded` gets a `retry_after` of 60 seconds; callers with a
+    /// `Retry-After` header should overwrite it.
+    pub fn from_status(
+        status: reqwest::StatusCode,
+        message: String,
+        error_type: Option<String>,
+    ) -> TinifyError {
+        let status = status.as_u16();
+        match status {
+            401 => {
+                if message.contains("credentials") {
+                    TinifyError::InvalidApiKey
+                } else {
+                    TinifyError::AccountError {
+                        message,
+                        error_type,
+                        status: Some(status),
+                    }
+                }
+            }
+            429 => {
+                if is_quota_error(error_type.as_deref(), &message) {
+                    TinifyError::QuotaExceeded {
+                        message,
+                        error_type,
+                    }
+                } else {
+                    TinifyError::RateLimitExceeded {
+                        retry_after: DEFAULT_RETRY_AFTER,
+                        message,
+                        error_type,
+                    }
+                }
+            }
+            400..=499 => TinifyError::ClientError {
+                message,
+                error_type,
+                status: Some(status),
+            },
+            500..=599 => TinifyError::ServerError {
+                message,
+                error_type,
+                status: Some(status),
+            },
+            _ => TinifyError::UnknownError {
+                message,
+                status: Some(status),
+            },
+        }
+    }
+
+    /// Capture this error as a cloneable, serializable [`ErrorSnapshot`]
+    ///
+    /// Useful for keeping errors around after the fact, e.g. in batch reports or logs.
+    pub fn to_snapshot(&self) -> ErrorSnapshot {
+        let message = match self {
+            // The snapshot has no source chain, so keep the last attempt's error
+            TinifyError::RetriesExhausted { source, .. } => {
+                format!("{}: {}", self, source.to_snapshot().message)
+            }
+            _ => self.to_string(),
+        };
+        ErrorSnapshot {
+            kind: self.kind(),
+            message,
+            status: self.status_code(),
+        }
+    }
+
+    /// Name of the variant, e.g. `"ClientError"`
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            TinifyError::InvalidApiKey => "InvalidApiKey",
+            TinifyError::QuotaExceeded { .. } => "QuotaExceeded",
+            TinifyError::FileTooLarge { .. } => "FileTooLarge",
+            TinifyError::EmptyInput => "EmptyInput",
+            TinifyError::UnsupportedFormat { .. } => "UnsupportedFormat",
+            TinifyError::FormatNotAvailable { .. } => "FormatNotAvailable",
+            TinifyError::FileNotFound { .. } => "FileNotFound",
+            TinifyError::RateLimitExceeded { .. } => "RateLimitExceeded",
+            TinifyError::InvalidDimensions { .. } => "InvalidDimensions",
+            TinifyError::InvalidBackground { .. } => "InvalidBackground",
+            TinifyError::InvalidOptions { .. } => "InvalidOptions",
+            TinifyError::ClientNotInitialized => "ClientNotInitialized",
+            TinifyError::AccountError { .. } => "AccountError",
+            TinifyError::ClientError { .. } => "ClientError",
+            TinifyError::ServerError { .. } => "ServerError",
+            TinifyError::ConnectionError(_) => "ConnectionError",
+            TinifyError::IoError(_) => "IoError",
+            TinifyError::JsonError(_) => "JsonError",
+            TinifyError::ChecksumMismatch { .. } => "ChecksumMismatch",
+            TinifyError::Base64Error(_) => "Base64Error",
+            TinifyError::InvalidDataUri { .. } => "InvalidDataUri",
+            TinifyError::InvalidUrl { .. } => "InvalidUrl",
+            TinifyError::UrlParseError(_) => "UrlParseError",
+            TinifyError::Timeout { .. } => "Timeout",
+            TinifyError::RetriesExhausted { .. } => "RetriesExhausted",
+            TinifyError::UnknownError { .. } => "UnknownError",
+        }
+    }
+
+    /// HTTP status code of the response that caused this error, if any
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            TinifyError::AccountError { status, .. }
+            | TinifyError::ClientError { status, .. }
+            | TinifyError::ServerError { status, .. }
+            | TinifyError::UnknownError { status, .. } => *status,
+            TinifyError::QuotaExceeded { .. } | TinifyError::RateLimitExceeded { .. } => Some(429),
+            TinifyError::ConnectionError(err) => err.status().map(|s| s.as_u16()),
+            TinifyError::RetriesExhausted { source, .. } => source.status_code(),
+            _ => None,
+        }
+    }
+}
+
+/// Owned summary of a [`TinifyError`] that can be cloned, stored and serialized
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorSnapshot {
+    /// Name of the error variant, e.g. `"ClientError"`
+    pub kind: &'static str,
+    /// Human-readable error message
+    pub message: String,
+    /// HTTP status code, if the error came from an API response
+    pub status: Option<u16>,
+}
+
+fn is_quota_error(error_type: Option<&str>, message: &str) -> bool {
+    let message = message.to_lowercase();
+    if message.contains("monthly limit") || message.contains("quota") {
+        return true;
+    }
+    error_type.is_some_and(|error_type| !error_type.eq_ignore_ascii_case("TooManyRequests"))
+}
+
+fn describe_reqwest_error(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        return format!("Request timed out: {}", err);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if err.is_connect() {
+        return format!("Failed to connect: {}", err);
+    }
+    if err.is_request() {
+        format!("Request error: {}", err)
+    } else {
+        format!("Connection error: {}", err)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, TinifyError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_snapshot_serializes_to_json() {
+        let err = TinifyError::ClientError {
+            message: "File is empty".to_string(),
+            error_type: Some("BadSignature".to_string()),
+            status: Some(400),
+        };
+
+        let snapshot = err.to_snapshot();
+        assert_eq!(snapshot.clone(), snapshot);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "ClientError",
+                "message": "Client error: File is empty",
+                "status": 400,
+            })
+        );
+
+        let snapshot = TinifyError::InvalidApiKey.to_snapshot();
+        assert_eq!(snapshot.kind, "InvalidApiKey");
+        assert_eq!(snapshot.status, None);
+    }
+
+    #[test]
+    fn test_retries_exhausted_reports_source_once() {
+        let err = TinifyError::RetriesExhausted {
+            attempts: 3,
+            elapsed: Duration::from_millis(1500),
+            source: Box::new(TinifyError::ServerError {
+                message: "Bad gateway".to_string(),
+                error_type: None,
+                status: Some(502),
+            }),
+        };
+
+        assert_eq!(err.to_string(), "Giving up after 3 attempts over 1.5s");
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "Server error: Bad gateway");
+        assert_eq!(
+            err.to_snapshot().message,
+            "Giving up after 3 attempts over 1.5s: Server error: Bad gateway"
+        );
+    }
+
+    #[test]
+    fn test_from_status_classifies_each_band() {
+        let classify = |status: u16, message: &str, error_type: Option<&str>| {
+            TinifyError::from_status(
+                reqwest::StatusCode::from_u16(status).unwrap(),
+                message.to_string(),
+                error_type.map(String::from),
+            )
+        };
+
+        assert!(matches!(
+            classify(
+                401,
+                "Provided credentials are invalid",
+                Some("Unauthorized")
+            ),
+            TinifyError::InvalidApiKey
+        ));
+        assert!(matches!(
+            classify(401, "Account suspended", Some("Unauthorized")),
+            TinifyError::AccountError {
+                status: Some(401),
+                ..
+            }
+        ));
+        assert!(matches!(
+            classify(429, "Slow down", Some("TooManyRequests")),
+            TinifyError::RateLimitExceeded {
+                retry_after: DEFAULT_RETRY_AFTER,
+                ..
+            }
+        ));
+        assert!(matches!(
+            classify(429, "Your monthly limit has been exceeded", None),
+            TinifyError::QuotaExceeded { .. }
+        ));
+        // The body Tinify actually sends for an exhausted monthly quota
+        assert!(matches!(
+            classify(
+                429,
+                "Your monthly limit has been exceeded",
+                Some("TooManyRequests")
+            ),
+            TinifyError::QuotaExceeded { .. }
+        ));
+        assert!(matches!(
+            classify(415, "File type is not supported", Some("Unsupported")),
+            TinifyError::ClientError {
+                status: Some(415),
+                ..
+            }
+        ));
+        assert!(matches!(
+            classify(503, "Unavailable", None),
+            TinifyError::ServerError {
+                status: Some(503),
+                ..
+            }
+        ));
+        assert!(matches!(
+            classify(302, "Found", None),
+            TinifyError::UnknownError {
+                status: Some(302),
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+use tokio::io::AsyncRead;
+
+/// Image to upload, for code that only knows at runtime where the image comes from
+///
+/// Passed to [`Tinify::source`](crate::Tinify::source), which dispatches to the matching
+/// `source_from_*` method.
+pub enum ImageInput {
+    /// Local file, see [`Tinify::source_from_file`](crate::Tinify::source_from_file)
+    #[cfg(feature = "fs")]
+    File(PathBuf),
+    /// Image data in memory, see [`Tinify::source_from_buffer`](crate::Tinify::source_from_buffer)
+    Buffer(Vec<u8>),
+    /// Public image URL, see [`Tinify::source_from_url`](crate::Tinify::source_from_url)
+    Url(String),
+    /// Streamed image data, see [`Tinify::source_from_stream`](crate::Tinify::source_from_stream)
+    Reader {
+        reader: Box<dyn AsyncRead + Send + Sync + Unpin>,
+        content_type: String,
+    },
+}
+
+impl std::fmt::Debug for ImageInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "fs")]
+            ImageInput::File(path) => f.debug_tuple("File").field(path).finish(),
+            ImageInput::Buffer(data) => f.debug_tuple("Buffer").field(&data.len()).finish(),
+            ImageInput::Url(url) => f.debug_tuple("Url").field(url).finish(),
+            ImageInput::Reader { content_type, .. } => f
+                .debug_struct("Reader")
+                .field("content_type", content_type)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl From<PathBuf> for ImageInput {
+    fn from(path: PathBuf) -> Self {
+        ImageInput::File(path)
+    }
+}
+
+impl From<Vec<u8>> for ImageInput {
+    fn from(data: Vec<u8>) -> Self {
+        ImageInput::Buffer(data)
+    }
+}
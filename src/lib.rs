@@ -1,29 +1,64 @@
+#[cfg(feature = "fs")]
+mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
+mod config;
 mod error;
+mod input;
 mod options;
+mod pricing;
+#[cfg(feature = "record")]
+mod record;
 mod result;
 mod source;
 
-pub use client::{Client, ClientBuilder, RateLimit, RetryConfig};
-pub use error::{Result, TinifyError};
+#[cfg(feature = "fs")]
+pub use batch::{BatchFailure, BatchItem, BatchReport, BatchSuccess};
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::IpVersion;
+pub use client::{
+    Client, ClientBuilder, ClientStats, RateLimit, RateLimiterRegistry, RequestBody,
+    RequestOptions, RetryBudget, RetryConfig, Sleeper, TokioSleeper,
+};
+pub use config::TinifyConfig;
+pub use error::{ErrorSnapshot, Result, TinifyError};
+pub use input::ImageInput;
 pub use options::{
-    ConvertOptions, GCSOptions, ImageFormat, PreserveMetadata, PreserveOptions, ResizeMethod,
-    ResizeOptions, S3Options, StoreOptions, StoreRequest,
+    ConvertOptions, GCSOptions, GCSOptionsBuilder, GcsMetadata, ImageFormat, InputFormat,
+    PreserveMetadata, PreserveOptions, ResizeMethod, ResizeOptions, S3Acl, S3Options,
+    S3OptionsBuilder, StoreOptions, StoreRequest, TransformOptions,
 };
+pub use pricing::{Plan, PricingTier};
+#[cfg(feature = "record")]
+pub use record::{RecordSink, RecordedExchange, TracingSink};
+#[cfg(not(target_arch = "wasm32"))]
+pub use reqwest::Certificate;
 pub use result::TinifyResult;
-pub use source::Source;
+#[cfg(feature = "fs")]
+pub use source::ResponsiveImageSet;
+pub use source::{Processable, Source};
+pub use tokio_util::sync::CancellationToken;
 
 // Main exports - don't re-export here as they're defined later in this module
 
+use base64::Engine;
 use mime::Mime;
 use serde_json::json;
-use std::{path::Path, sync::Arc};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::Arc;
 use tokio::io::AsyncRead;
 use tracing::{info, instrument};
 
-const SHRINK_ENDPOINT: &str = "https://api.tinify.com/shrink";
 const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024; // 5MB
-const SUPPORTED_FORMATS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+#[cfg(feature = "fs")]
+const SUPPORTED_FORMATS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif"];
+/// Extensions of documents that are never images, rejected before fetching a URL
+const NON_IMAGE_EXTENSIONS: &[&str] = &[
+    "html", "htm", "xhtml", "php", "asp", "aspx", "jsp", "pdf", "txt", "json", "xml",
+];
 
 /// Main Tinify client for image compression and optimization
 ///
@@ -70,6 +105,54 @@ impl Tinify {
         })
     }
 
+    /// Create a Tinify client configured from environment variables
+    ///
+    /// Reads the API key from `TINIFY_API_KEY`, and optionally the app identifier from
+    /// `TINIFY_APP_IDENTIFIER` and the requests-per-minute limit from `TINIFY_RPM`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::InvalidApiKey`] if `TINIFY_API_KEY` is unset or empty, and
+    /// [`TinifyError::InvalidOptions`] if `TINIFY_RPM` is not a positive integer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::from_env()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Configure a client like [`Tinify::from_env`], looking variables up with `var`
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let api_key = var("TINIFY_API_KEY")
+            .filter(|key| !key.trim().is_empty())
+            .ok_or(TinifyError::InvalidApiKey)?;
+        let mut builder = Self::builder().api_key(api_key);
+
+        if let Some(app_identifier) = var("TINIFY_APP_IDENTIFIER") {
+            builder = builder.app_identifier(app_identifier);
+        }
+
+        if let Some(rpm) = var("TINIFY_RPM") {
+            let rpm = rpm
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|rpm| *rpm > 0)
+                .ok_or_else(|| TinifyError::InvalidOptions {
+                    message: format!("TINIFY_RPM must be a positive integer, got `{}`", rpm),
+                })?;
+            builder = builder.requests_per_minute(rpm);
+        }
+
+        builder.build()
+    }
+
     /// Create a Tinify client using the builder pattern
     ///
     /// # Examples
@@ -83,6 +166,7 @@ impl Tinify {
     ///     base_delay: Duration::from_millis(200),
     ///     max_delay: Duration::from_secs(30),
     ///     backoff_factor: 2.0,
+    ///     max_total_duration: Some(Duration::from_secs(120)),
     /// };
     ///
     /// let client = Tinify::builder()
@@ -98,6 +182,7 @@ impl Tinify {
         TinifyBuilder::new()
     }
 
+    #[cfg(feature = "fs")]
     fn validate_image_format<P: AsRef<Path>>(path: P) -> Result<()> {
         let path = path.as_ref();
         let extension = path
@@ -115,17 +200,92 @@ impl Tinify {
         Ok(())
     }
 
-    fn validate_dimensions(width: Option<u32>, height: Option<u32>) -> Result<()> {
-        match (width, height) {
-            (None, None) => Err(TinifyError::InvalidDimensions { width, height }),
-            (Some(0), _) => Err(TinifyError::InvalidDimensions { width, height }),
-            (_, Some(0)) => Err(TinifyError::InvalidDimensions { width, height }),
-            (Some(w), _) if w > 10000 => Err(TinifyError::InvalidDimensions { width, height }),
-            (_, Some(h)) if h > 10000 => Err(TinifyError::InvalidDimensions { width, height }),
+    /// Turn the response to an upload into a [`Source`] at its `Location`
+    ///
+    /// Also captures the dimensions of the upload, from `output.width` and
+    /// `output.height` in the JSON body, or else the `Image-Width` and `Image-Height`
+    /// headers, if sent.
+    async fn source_from_upload_response(&self, response: reqwest::Response) -> Result<Source> {
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+        let location = header("Location")
+            .ok_or_else(|| TinifyError::UnknownError {
+                message: "Missing Location header in server response".to_string(),
+                status: None,
+            })?
+            .to_string();
+        let dimension = |name: &str| header(name).and_then(|v| v.trim().parse::<u32>().ok());
+        let header_dimensions = dimension("Image-Width").zip(dimension("Image-Height"));
+
+        let body = response.bytes().await.unwrap_or_default();
+        let body_dimensions = serde_json::from_slice::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|body| {
+                let output = body.get("output")?;
+                let dimension = |name: &str| {
+                    output
+                        .get(name)?
+                        .as_u64()
+                        .and_then(|v| u32::try_from(v).ok())
+                };
+                dimension("width").zip(dimension("height"))
+            });
+
+        Ok(Source::new(location, Arc::clone(&self.client))
+            .with_original_dimensions(body_dimensions.or(header_dimensions)))
+    }
+
+    /// Reject inputs that are empty or exceed the API's upload limit
+    fn validate_input_size(size: u64) -> Result<()> {
+        if size == 0 {
+            return Err(TinifyError::EmptyInput);
+        }
+        if size > MAX_FILE_SIZE {
+            return Err(TinifyError::FileTooLarge {
+                size,
+                max_size: MAX_FILE_SIZE,
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject URLs whose path names a document that is never an image, such as a
+    /// web page or PDF
+    ///
+    /// Other extensions are allowed through, since image hosts serve images from
+    /// extensionless paths and under unusual names; the API answers 415 for anything
+    /// that turns out not to be an image.
+    fn validate_url_format(url: &url::Url) -> Result<()> {
+        match Self::url_extension(url) {
+            Some(ext) if NON_IMAGE_EXTENSIONS.contains(&ext.as_str()) => {
+                Err(TinifyError::UnsupportedFormat { format: ext })
+            }
             _ => Ok(()),
         }
     }
 
+    /// Lowercased extension of the last path segment of `url`, if it has one
+    fn url_extension(url: &url::Url) -> Option<String> {
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_lowercase())
+    }
+
+    fn validate_dimensions(width: Option<u32>, height: Option<u32>, max: u32) -> Result<()> {
+        let invalid = match (width, height) {
+            (None, None) => true,
+            (Some(0), _) | (_, Some(0)) => true,
+            (Some(w), _) if w > max => true,
+            (_, Some(h)) if h > max => true,
+            _ => false,
+        };
+
+        if invalid {
+            return Err(TinifyError::InvalidDimensions { width, height, max });
+        }
+        Ok(())
+    }
+
     /// Create a Source object from a local file
     ///
     /// Validates the file existence, size, and format before uploading to Tinify.
@@ -145,29 +305,32 @@ impl Tinify {
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
+    #[cfg(feature = "fs")]
     #[instrument(skip(self), fields(path = %path.as_ref().display()))]
     pub async fn source_from_file<P: AsRef<Path>>(&self, path: P) -> Result<Source> {
         let path = path.as_ref();
         info!("Creating source from file: {}", path.display());
 
+        let data = Self::read_image_file(path).await?;
+        self.source_from_buffer(data).await
+    }
+
+    /// Read an image file after checking that it exists, isn't too large and has a
+    /// supported extension
+    #[cfg(feature = "fs")]
+    async fn read_image_file(path: &Path) -> Result<Vec<u8>> {
         if !path.exists() {
             return Err(TinifyError::FileNotFound {
                 path: path.to_path_buf(),
             });
         }
 
-        let metadata = tokio::fs::metadata(path).await?;
-        if metadata.len() > MAX_FILE_SIZE {
-            return Err(TinifyError::FileTooLarge {
-                size: metadata.len(),
-                max_size: MAX_FILE_SIZE,
-            });
-        }
-
         Self::validate_image_format(path)?;
 
-        let data = tokio::fs::read(path).await?;
-        self.source_from_buffer(data).await
+        let metadata = tokio::fs::metadata(path).await?;
+        Self::validate_input_size(metadata.len())?;
+
+        Ok(tokio::fs::read(path).await?)
     }
 
     /// Create a Source object from image data in memory
@@ -192,24 +355,169 @@ impl Tinify {
     pub async fn source_from_buffer(&self, data: Vec<u8>) -> Result<Source> {
         info!("Creating source from buffer of {} bytes", data.len());
 
-        if data.len() as u64 > MAX_FILE_SIZE {
-            return Err(TinifyError::FileTooLarge {
-                size: data.len() as u64,
-                max_size: MAX_FILE_SIZE,
-            });
-        }
+        Self::validate_input_size(data.len() as u64)?;
+
+        let response = self
+            .client
+            .post(
+                self.client.shrink_endpoint(),
+                Some(RequestBody::image(data)),
+            )
+            .await?;
 
-        let response = self.client.post(SHRINK_ENDPOINT, Some(data)).await?;
+        self.source_from_upload_response(response).await
+    }
 
-        let location = response
-            .headers()
-            .get("Location")
-            .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| TinifyError::UnknownError {
-                message: "Missing Location header in server response".to_string(),
+    /// Create a Source object from image data with an explicit content type
+    ///
+    /// Like [`Tinify::source_from_buffer`], but sends `content_type` as the upload's
+    /// `Content-Type` header instead of leaving detection to the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Binary data of the image
+    /// * `content_type` - MIME type of the image (e.g., "image/png")
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let image_data = std::fs::read("input.webp").unwrap();
+    /// let source = client
+    ///     .source_from_buffer_with_type(image_data, "image/webp")
+    ///     .await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, data), fields(data_size = data.len(), content_type = %content_type))]
+    pub async fn source_from_buffer_with_type(
+        &self,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<Source> {
+        info!(
+            "Creating source from buffer of {} bytes with content type: {}",
+            data.len(),
+            content_type
+        );
+
+        Self::validate_input_size(data.len() as u64)?;
+
+        let _mime: Mime = content_type
+            .parse()
+            .map_err(|_| TinifyError::UnsupportedFormat {
+                format: content_type.to_string(),
+            })?;
+
+        let response = self
+            .client
+            .post(
+                self.client.shrink_endpoint(),
+                Some(RequestBody::Image {
+                    data,
+                    content_type: Some(content_type.to_string()),
+                }),
+            )
+            .await?;
+
+        self.source_from_upload_response(response).await
+    }
+
+    /// Create a Source object from a base64-encoded string
+    ///
+    /// Decodes the standard base64 payload and uploads it via [`Tinify::source_from_buffer`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Base64-encoded image data (without a `data:` prefix)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_base64("iVBORw0KGgo...").await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, data), fields(data_len = data.len()))]
+    pub async fn source_from_base64(&self, data: &str) -> Result<Source> {
+        info!("Creating source from base64 string");
+
+        let bytes = base64::engine::general_purpose::STANDARD.decode(data.trim())?;
+        self.source_from_buffer(bytes).await
+    }
+
+    /// Create a Source object from a `data:` URI
+    ///
+    /// Accepts URIs of the form `data:image/png;base64,...`. The media type must be
+    /// an `image/*` type and the payload must be base64-encoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - Data URI containing the image
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client
+    ///     .source_from_data_uri("data:image/png;base64,iVBORw0KGgo...")
+    ///     .await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, uri), fields(uri_len = uri.len()))]
+    pub async fn source_from_data_uri(&self, uri: &str) -> Result<Source> {
+        info!("Creating source from data URI");
+
+        let (_mime, bytes) = Self::parse_data_uri(uri)?;
+        self.source_from_buffer(bytes).await
+    }
+
+    fn parse_data_uri(uri: &str) -> Result<(Mime, Vec<u8>)> {
+        let rest = uri
+            .trim()
+            .strip_prefix("data:")
+            .ok_or_else(|| TinifyError::InvalidDataUri {
+                message: "missing 'data:' prefix".to_string(),
+            })?;
+
+        let (header, payload) =
+            rest.split_once(',')
+                .ok_or_else(|| TinifyError::InvalidDataUri {
+                    message: "missing ',' separator".to_string(),
+                })?;
+
+        let media_type =
+            header
+                .strip_suffix(";base64")
+                .ok_or_else(|| TinifyError::InvalidDataUri {
+                    message: "only base64-encoded data URIs are supported".to_string(),
+                })?;
+
+        let mime: Mime = media_type
+            .parse()
+            .map_err(|_| TinifyError::UnsupportedFormat {
+                format: media_type.to_string(),
             })?;
 
-        Ok(Source::new(location.to_string(), Arc::clone(&self.client)))
+        if mime.type_() != mime::IMAGE {
+            return Err(TinifyError::UnsupportedFormat {
+                format: media_type.to_string(),
+            });
+        }
+
+        let bytes = base64::engine::general_purpose::STANDARD.decode(payload)?;
+        Ok((mime, bytes))
     }
 
     /// Create a Source object from a URL
@@ -234,21 +542,36 @@ impl Tinify {
         let url_str = url.as_ref();
         info!("Creating source from URL: {}", url_str);
 
-        // Basic URL validation
-        let _parsed_url = url::Url::parse(url_str)?;
+        // Tinify can only fetch images over HTTP(S)
+        let parsed_url = url::Url::parse(url_str)?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(TinifyError::InvalidUrl {
+                message: format!(
+                    "unsupported scheme `{}`, only http and https are allowed",
+                    parsed_url.scheme()
+                ),
+            });
+        }
 
-        let body = serde_json::to_vec(&json!({ "source": { "url": url_str } }))?;
-        let response = self.client.post(SHRINK_ENDPOINT, Some(body)).await?;
+        Self::validate_url_format(&parsed_url)?;
 
-        let location = response
-            .headers()
-            .get("Location")
-            .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| TinifyError::UnknownError {
-                message: "Missing Location header in server response".to_string(),
+        let body = RequestBody::json(&json!({ "source": { "url": url_str } }))?;
+        let response = self
+            .client
+            .post(self.client.shrink_endpoint(), Some(body))
+            .await
+            .map_err(|e| match e {
+                // The API answers 415 when the fetched resource is not an image
+                TinifyError::ClientError {
+                    status: Some(415), ..
+                } => TinifyError::UnsupportedFormat {
+                    format: Self::url_extension(&parsed_url)
+                        .unwrap_or_else(|| "unknown".to_string()),
+                },
+                other => other,
             })?;
 
-        Ok(Source::new(location.to_string(), Arc::clone(&self.client)))
+        self.source_from_upload_response(response).await
     }
 
     /// Create a Source object from a stream
@@ -291,63 +614,761 @@ impl Tinify {
 
         let response = self
             .client
-            .post_stream(SHRINK_ENDPOINT, stream, content_type)
+            .post_stream(self.client.shrink_endpoint(), stream, content_type)
             .await?;
 
-        let location = response
-            .headers()
-            .get("Location")
-            .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| TinifyError::UnknownError {
-                message: "Missing Location header in server response".to_string(),
-            })?;
-
-        Ok(Source::new(location.to_string(), Arc::clone(&self.client)))
+        self.source_from_upload_response(response).await
     }
 
-    /// Get the API key used by this client
-    pub fn api_key(&self) -> &str {
-        self.client.api_key()
-    }
-}
+    /// Create a Source object from a stream of known length
+    ///
+    /// Like [`Tinify::source_from_stream`], but sends a `Content-Length` header instead
+    /// of using chunked transfer encoding, which some proxies reject. Exactly `len`
+    /// bytes are read from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::FileTooLarge`] without reading anything if `len` exceeds
+    /// the maximum upload size, or [`TinifyError::EmptyInput`] if it is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    /// use tokio::fs::File;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let file = File::open("input.png").await?;
+    /// let len = file.metadata().await?.len();
+    /// let source = client.source_from_reader_with_len(file, len, "image/png").await?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, reader), fields(content_type = %content_type))]
+    pub async fn source_from_reader_with_len<R>(
+        &self,
+        reader: R,
+        len: u64,
+        content_type: &str,
+    ) -> Result<Source>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        info!(
+            "Creating source from {} byte stream with content type: {}",
+            len, content_type
+        );
 
-pub struct TinifyBuilder {
-    inner: ClientBuilder,
-}
+        Self::validate_input_size(len)?;
 
-impl TinifyBuilder {
-    pub fn new() -> Self {
-        Self {
-            inner: ClientBuilder::new(),
-        }
-    }
+        let _mime: Mime = content_type
+            .parse()
+            .map_err(|_| TinifyError::UnsupportedFormat {
+                format: content_type.to_string(),
+            })?;
 
-    pub fn api_key<S: Into<String>>(mut self, key: S) -> Self {
-        self.inner = self.inner.api_key(key);
-        self
-    }
+        let response = self
+            .client
+            .post_stream_with_len(self.client.shrink_endpoint(), reader, len, content_type)
+            .await?;
 
-    pub fn app_identifier<S: Into<String>>(mut self, identifier: S) -> Self {
-        self.inner = self.inner.app_identifier(identifier);
-        self
+        self.source_from_upload_response(response).await
     }
 
-    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
-        self.inner = self.inner.timeout(timeout);
-        self
+    /// Create a Source object from any kind of [`ImageInput`]
+    ///
+    /// Dispatches to [`Tinify::source_from_file`], [`Tinify::source_from_buffer`],
+    /// [`Tinify::source_from_url`] or [`Tinify::source_from_stream`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ImageInput, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let input = match std::env::args().nth(1) {
+    ///     Some(arg) if arg.starts_with("https://") => ImageInput::Url(arg),
+    ///     Some(arg) => ImageInput::File(arg.into()),
+    ///     None => ImageInput::Buffer(std::fs::read("input.png").unwrap()),
+    /// };
+    /// let source = client.source(input).await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn source(&self, input: ImageInput) -> Result<Source> {
+        match input {
+            #[cfg(feature = "fs")]
+            ImageInput::File(path) => self.source_from_file(path).await,
+            ImageInput::Buffer(data) => self.source_from_buffer(data).await,
+            ImageInput::Url(url) => self.source_from_url(&url).await,
+            ImageInput::Reader {
+                reader,
+                content_type,
+            } => self.source_from_stream(reader, &content_type).await,
+        }
     }
 
-    pub fn retry_config(mut self, config: RetryConfig) -> Self {
-        self.inner = self.inner.retry_config(config);
-        self
-    }
+    /// Compress image data, keeping the original unless the result is small enough
+    ///
+    /// Already-optimized images can come back *larger* than they were. This uploads
+    /// `data`, downloads the result and compares sizes: if the result saves less than
+    /// `min_savings` (a fraction of the original size, e.g. `0.05` for 5%), the original
+    /// bytes are returned with [`CompressedImage::skipped`] set. Use `0.0` to only reject
+    /// results that are larger than the input. The upload still counts as a compression.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::InvalidOptions`] if `min_savings` is not within `0.0..1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let image = std::fs::read("input.png").unwrap();
+    /// let compressed = client.compress_buffer(image, 0.05).await?;
+    /// if compressed.skipped {
+    ///     println!("Kept original, compression saved less than 5%");
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, data), fields(data_size = data.len()))]
+    pub async fn compress_buffer(
+        &self,
+        data: Vec<u8>,
+        min_savings: f64,
+    ) -> Result<CompressedImage> {
+        if !(0.0..1.0).contains(&min_savings) {
+            return Err(TinifyError::InvalidOptions {
+                message: format!("min_savings must be within 0.0..1.0, got {}", min_savings),
+            });
+        }
 
-    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
-        self.inner = self.inner.rate_limit(limit);
-        self
+        let original_size = data.len() as u64;
+        let compressed = self
+            .source_from_buffer(data.clone())
+            .await?
+            .to_buffer()
+            .await?;
+        let compressed_size = compressed.len() as u64;
+
+        let savings = if original_size == 0 {
+            0.0
+        } else {
+            1.0 - compressed_size as f64 / original_size as f64
+        };
+        let skipped = compressed_size >= original_size || savings < min_savings;
+        if skipped {
+            info!(
+                "Keeping original: result is {} bytes for a {} byte input",
+                compressed_size, original_size
+            );
+        }
+
+        Ok(CompressedImage {
+            data: if skipped { data } else { compressed },
+            skipped,
+            original_size,
+            compressed_size,
+        })
     }
 
-    pub fn max_retry_attempts(mut self, attempts: u32) -> Self {
+    /// Compress a file, keeping the result only if it is meaningfully smaller
+    ///
+    /// Reads and validates the file like [`Tinify::source_from_file`], then behaves like
+    /// [`Tinify::compress_buffer`]: if the result saves less than `min_savings`, the
+    /// input is reported as already optimized through [`CompressedImage::skipped`] and
+    /// `data` holds the original bytes, so there is nothing worth writing out.
+    ///
+    /// The upload still counts as a compression whether or not the result is kept;
+    /// this saves disk space and bandwidth downstream, not quota.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let result = client.source_from_file_if_beneficial("input.png", 0.05).await?;
+    /// if result.skipped {
+    ///     println!("input.png is already optimized");
+    /// } else {
+    ///     std::fs::write("output.png", &result.data)?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # });
+    /// ```
+    #[cfg(feature = "fs")]
+    #[instrument(skip(self), fields(path = %path.as_ref().display()))]
+    pub async fn source_from_file_if_beneficial<P: AsRef<Path>>(
+        &self,
+        path: P,
+        min_savings: f64,
+    ) -> Result<CompressedImage> {
+        let path = path.as_ref();
+        info!("Compressing file if beneficial: {}", path.display());
+
+        let data = Self::read_image_file(path).await?;
+        self.compress_buffer(data, min_savings).await
+    }
+
+    /// Compress a file and store the result in S3 in one call, returning its location
+    ///
+    /// Combines [`Tinify::source_from_file`] and [`Source::store`] for the common
+    /// "optimize and push to a bucket" case. Use those directly to apply further
+    /// operations before storing or to store to several destinations.
+    ///
+    /// # Errors
+    ///
+    /// Fails like the two calls it combines, or with [`TinifyError::UnknownError`] if
+    /// the store response doesn't include the `Location` of the stored object.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{S3Options, Tinify};
+    ///
+    /// let client = Tinify::from_env()?;
+    /// let options = S3Options::new(
+    ///     "access-key".to_string(),
+    ///     "secret".to_string(),
+    ///     "us-east-1".to_string(),
+    ///     "my-bucket/images/photo.png".to_string(),
+    /// );
+    /// let location = client.compress_file_to_s3("photo.png", options).await?;
+    /// println!("Stored at {}", location);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[cfg(feature = "fs")]
+    pub async fn compress_file_to_s3<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: S3Options,
+    ) -> Result<String> {
+        self.compress_file_to_store(path.as_ref(), StoreOptions::S3(options))
+            .await
+    }
+
+    /// Compress a file and store the result in Google Cloud Storage, returning its location
+    ///
+    /// The GCS counterpart of [`Tinify::compress_file_to_s3`].
+    #[cfg(feature = "fs")]
+    pub async fn compress_file_to_gcs<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: GCSOptions,
+    ) -> Result<String> {
+        self.compress_file_to_store(path.as_ref(), StoreOptions::GCS(options))
+            .await
+    }
+
+    #[cfg(feature = "fs")]
+    #[instrument(skip(self, options), fields(path = %path.display()))]
+    async fn compress_file_to_store(&self, path: &Path, options: StoreOptions) -> Result<String> {
+        // Checked before uploading so invalid options don't cost a compression
+        options.validate()?;
+        let result = self.source_from_file(path).await?.store(options).await?;
+        result
+            .location()
+            .map(str::to_string)
+            .ok_or_else(|| TinifyError::UnknownError {
+                message: "Missing Location header in store response".to_string(),
+                status: None,
+            })
+    }
+
+    /// Get the API key used by this client
+    pub fn api_key(&self) -> &str {
+        self.client.api_key()
+    }
+
+    /// Number of requests that can currently be made without waiting on the rate limiter
+    ///
+    /// This is a best-effort estimate derived from the last granted permit and the
+    /// replenish rate, intended for pacing work in schedulers. Concurrent requests may
+    /// consume permits between this call and the next request.
+    pub fn rate_limit_available(&self) -> usize {
+        self.client.rate_limit_available()
+    }
+
+    /// Estimated monthly cost in USD of `count` compressions under `plan`
+    ///
+    /// Pair it with [`TinifyResult::compression_count`] to warn before overage charges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::{Plan, Tinify};
+    ///
+    /// assert_eq!(Tinify::estimate_cost(500, &Plan::pay_as_you_go()), 0.0);
+    /// assert!(Tinify::estimate_cost(1_000, &Plan::pay_as_you_go()) > 0.0);
+    /// ```
+    pub fn estimate_cost(count: u32, plan: &Plan) -> f64 {
+        plan.estimate_cost(count)
+    }
+
+    /// Effective retry settings, e.g. for logging the configuration at startup
+    pub fn retry_config(&self) -> &RetryConfig {
+        self.client.retry_config()
+    }
+
+    /// Effective rate limit
+    ///
+    /// Zero values passed to the builder are reported as the defaults that replaced
+    /// them. With a shared [`RateLimiterRegistry`], this is the limit of the shared
+    /// limiter.
+    pub fn rate_limit(&self) -> &RateLimit {
+        self.client.rate_limit()
+    }
+
+    /// Requests, retries and bytes transferred by this client so far
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// client.source_from_file("input.png").await?.to_file("output.png").await?;
+    ///
+    /// let stats = client.stats();
+    /// println!("{} requests, {} bytes up, {} bytes down", stats.requests, stats.bytes_up, stats.bytes_down);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub fn stats(&self) -> ClientStats {
+        self.client.stats()
+    }
+
+    /// Check that the API is reachable and the key is accepted, returning the latency
+    ///
+    /// Makes a single request with a 5 second timeout and no retries, and doesn't use
+    /// a compression, which makes it suitable for readiness probes. Fails with
+    /// [`TinifyError::InvalidApiKey`] if the key is rejected, and with
+    /// [`TinifyError::Timeout`] or [`TinifyError::ConnectionError`] if the API can't
+    /// be reached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let latency = client.ping().await?;
+    /// println!("Tinify answered in {:?}", latency);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn ping(&self) -> Result<std::time::Duration> {
+        self.client.ping(PING_TIMEOUT).await
+    }
+
+    /// Handle whose requests follow `options`
+    ///
+    /// The handle shares the connection pool, rate limiter and stats with this one.
+    /// Options apply to every request made through it, including those made by the
+    /// [`Source`]s it creates and the results they return.
+    ///
+    /// # Examples
+    ///
+    /// Give a compress-and-download 10 seconds in total, including retries:
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use std::time::{Duration, Instant};
+    /// use tinify::{RequestOptions, Tinify, TinifyError};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let deadline = Instant::now() + Duration::from_secs(10);
+    /// let bounded = client.with_request_options(RequestOptions::new().deadline(deadline));
+    ///
+    /// match bounded.source_from_file("input.png").await?.to_buffer().await {
+    ///     Ok(data) => println!("Compressed to {} bytes", data.len()),
+    ///     Err(TinifyError::Timeout { elapsed }) => println!("Gave up after {:?}", elapsed),
+    ///     Err(err) => return Err(err),
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub fn with_request_options(&self, options: RequestOptions) -> Tinify {
+        Tinify {
+            client: Arc::new(self.client.with_request_options(options)),
+        }
+    }
+}
+
+/// Result of [`Tinify::compress_buffer`]
+#[derive(Debug, Clone)]
+pub struct CompressedImage {
+    /// Compressed image, or the original if the savings were below the threshold
+    pub data: Vec<u8>,
+    /// Whether the compressed result was discarded in favor of the original
+    pub skipped: bool,
+    pub original_size: u64,
+    /// Size of the result returned by the API, even if it was skipped
+    pub compressed_size: u64,
+}
+
+pub struct TinifyBuilder {
+    inner: ClientBuilder,
+    #[cfg(feature = "fs")]
+    api_key_file: Option<std::path::PathBuf>,
+}
+
+impl TinifyBuilder {
+    pub fn new() -> Self {
+        Self {
+            inner: ClientBuilder::new(),
+            #[cfg(feature = "fs")]
+            api_key_file: None,
+        }
+    }
+
+    pub fn api_key<S: Into<String>>(mut self, key: S) -> Self {
+        self.inner = self.inner.api_key(key);
+        self
+    }
+
+    /// Read the API key from a file when the client is built
+    ///
+    /// Meant for secrets mounted as files, e.g. `/run/secrets/tinify_key`. Surrounding
+    /// whitespace, including a trailing newline, is trimmed. `build()` fails if the
+    /// file is missing or empty. Takes precedence over [`api_key`](Self::api_key).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::builder()
+    ///     .api_key_file("/run/secrets/tinify_key")
+    ///     .build()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn api_key_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.api_key_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Identifier of your application, sent as the `User-Agent` header
+    ///
+    /// Must be a legal header value: visible ASCII characters without line breaks.
+    /// `build()` rejects anything else with [`TinifyError::InvalidOptions`].
+    pub fn app_identifier<S: Into<String>>(mut self, identifier: S) -> Self {
+        self.inner = self.inner.app_identifier(identifier);
+        self
+    }
+
+    /// Override the Tinify API base URL (defaults to `https://api.tinify.com`)
+    ///
+    /// Useful when routing requests through a proxy or gateway, or for testing
+    /// against a mock server.
+    pub fn base_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.inner = self.inner.base_url(url);
+        self
+    }
+
+    /// Add a header that is sent with every request
+    ///
+    /// Can be called multiple times to add several headers, e.g. a correlation id or a
+    /// gateway token. Setting `Authorization` is rejected by [`TinifyBuilder::build`]
+    /// since it would clobber the API key credentials, as are headers the client sets
+    /// itself (`User-Agent`, `Content-Type`, `Content-Length`, `Range`, `X-Request-Id`,
+    /// `Content-MD5` and `Accept-Encoding`), which would otherwise be sent twice.
+    pub fn default_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.inner = self.inner.default_header(name, value);
+        self
+    }
+
+    /// Send a unique `X-Request-Id` header with every request (disabled by default)
+    ///
+    /// The id is recorded on the request's tracing span and exposed via
+    /// [`TinifyResult::request_id`], making it easy to correlate client and server logs.
+    pub fn send_request_id(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.send_request_id(enabled);
+        self
+    }
+
+    /// Send a `Content-MD5` header with every request body (disabled by default)
+    ///
+    /// The header carries the base64 encoded MD5 digest of the body, letting strict
+    /// gateways and the server detect uploads corrupted in flight. Streamed uploads
+    /// are not hashed, since their body is not in memory up front.
+    pub fn content_md5(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.content_md5(enabled);
+        self
+    }
+
+    /// Capture the metadata of every request and its response (requires the
+    /// `record` feature)
+    ///
+    /// Each attempt, including retries, downloads and streamed uploads, is handed to
+    /// `sink` as a [`RecordedExchange`] with its method, URL, status and headers; bodies
+    /// are not captured and the API key is redacted. Use [`TracingSink`] to emit them
+    /// as structured log events, or a closure to collect them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::{Tinify, TracingSink};
+    ///
+    /// let client = Tinify::builder()
+    ///     .api_key("your-api-key")
+    ///     .record_sink(TracingSink)
+    ///     .build()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    #[cfg(feature = "record")]
+    pub fn record_sink<S: RecordSink + 'static>(mut self, sink: S) -> Self {
+        self.inner = self.inner.record_sink(sink);
+        self
+    }
+
+    /// Validate everything locally without calling the API (disabled by default)
+    ///
+    /// In dry-run mode every method performs its usual local validation (file existence,
+    /// size and format, URL scheme, resize dimensions, option serialization, ...) but no
+    /// request is sent and no quota is used. Uploads return a `Source` with a placeholder
+    /// location and operations return results with **no image data**: downloads yield an
+    /// empty buffer and `to_file` writes an empty file. Use this to check pipeline wiring
+    /// and option construction in CI.
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.dry_run(enabled);
+        self
+    }
+
+    /// Turn validation warnings into errors (disabled by default)
+    ///
+    /// By default, requests that are valid but probably a mistake only log a
+    /// warning. In strict mode they fail with [`TinifyError::InvalidOptions`]
+    /// instead, which suits CI and teams that want to enforce correct usage. The
+    /// escalated conditions are:
+    ///
+    /// - a background color set for a conversion to a format with transparency,
+    ///   which the API ignores
+    /// - a placeholder API key from the examples or docs, rejected by `build()`
+    /// - preserving an empty list of metadata, which has no effect
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.strict(enabled);
+        self
+    }
+
+    /// Resume interrupted downloads with HTTP range requests (disabled by default)
+    ///
+    /// Applies to [`Source::to_buffer`] and [`Source::to_file`]. If the connection drops
+    /// part-way through a download and the server advertises `Accept-Ranges: bytes`,
+    /// the remaining bytes are fetched with `Range: bytes=N-` instead of restarting.
+    /// Downloads are then requested with `Accept-Encoding: identity`, since offsets into
+    /// a gzip or brotli encoded body would not line up with the decoded bytes.
+    /// Leave this off for endpoints that don't support range requests.
+    pub fn resume_downloads(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.resume_downloads(enabled);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.inner = self.inner.timeout(timeout);
+        self
+    }
+
+    /// Timeout for downloading results, separate from the general request timeout
+    ///
+    /// Applies to the GET requests made by [`Source::to_buffer`] and `Source::to_file`,
+    /// so large results can take longer than uploads and API calls. Defaults to the
+    /// general [`TinifyBuilder::timeout`].
+    pub fn download_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.inner = self.inner.download_timeout(timeout);
+        self
+    }
+
+    /// Maximum number of idle connections kept open per host
+    ///
+    /// Defaults to reqwest's unlimited pool. Set this to roughly the number of
+    /// concurrent requests you make so bursts reuse warm connections instead of
+    /// paying for new TLS handshakes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.inner = self.inner.pool_max_idle_per_host(max_idle);
+        self
+    }
+
+    /// Size in bytes of the chunks read from streams for streaming uploads
+    ///
+    /// Defaults to 4 KiB. Larger chunks mean fewer, bigger writes and better throughput
+    /// for large or fast sources, at the cost of holding more of the upload in memory
+    /// at once. `build()` rejects a size of zero.
+    pub fn stream_chunk_size(mut self, size: usize) -> Self {
+        self.inner = self.inner.stream_chunk_size(size);
+        self
+    }
+
+    /// Largest JSON operation body, in bytes, sent for resize, convert, preserve and
+    /// store requests
+    ///
+    /// Defaults to 64 KiB, far above any real operation. It guards against runaway
+    /// values, e.g. a huge `headers` object in store options, independently of the
+    /// image size limit. Larger bodies fail with [`TinifyError::InvalidOptions`]
+    /// before anything is sent.
+    pub fn max_operation_body_size(mut self, size: usize) -> Self {
+        self.inner = self.inner.max_operation_body_size(size);
+        self
+    }
+
+    /// How long an idle pooled connection is kept before being closed
+    ///
+    /// Defaults to reqwest's 90 seconds. Raise it if calls come in bursts further
+    /// apart than that, to keep connections warm between them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.inner = self.inner.pool_idle_timeout(timeout);
+        self
+    }
+
+    /// Trust an additional root certificate
+    ///
+    /// Needed behind TLS-inspecting proxies or for private S3-compatible endpoints
+    /// whose certificates are signed by an internal CA. The system roots remain trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tinify::{Certificate, Tinify};
+    ///
+    /// let pem = std::fs::read("corporate-ca.pem").unwrap();
+    /// let client = Tinify::builder()
+    ///     .api_key("your-api-key")
+    ///     .add_root_certificate(Certificate::from_pem(&pem).unwrap())
+    ///     .build()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.inner = self.inner.add_root_certificate(certificate);
+        self
+    }
+
+    /// Skip TLS certificate validation entirely (disabled by default)
+    ///
+    /// # Security
+    ///
+    /// This makes every connection, including the one carrying your API key, open to
+    /// interception by anyone on the network path. Prefer
+    /// [`TinifyBuilder::add_root_certificate`] and only use this for local testing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.inner = self.inner.danger_accept_invalid_certs(accept);
+        self
+    }
+
+    /// Transparently decode gzip and brotli encoded responses (enabled by default)
+    ///
+    /// When enabled, requests advertise `Accept-Encoding: gzip, br` and results are
+    /// returned decoded. Disable it to receive the bytes exactly as served, e.g. to
+    /// re-serve them with their `Content-Encoding` unchanged.
+    pub fn decompress(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.decompress(enabled);
+        self
+    }
+
+    /// Bind outgoing connections to a local IP address
+    ///
+    /// Lets multi-homed hosts choose which interface, and so which egress path,
+    /// Tinify traffic uses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::builder()
+    ///     .api_key("your-api-key")
+    ///     .local_address(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)))
+    ///     .build()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn local_address(mut self, address: std::net::IpAddr) -> Self {
+        self.inner = self.inner.local_address(address);
+        self
+    }
+
+    /// Only connect over IPv4 or only over IPv6
+    ///
+    /// By default both are used. This is an escape hatch for broken dual-stack
+    /// networks, e.g. where IPv6 is advertised but unroutable and every request waits
+    /// for a connect timeout before falling back. Host names are resolved to
+    /// addresses of the chosen version only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ip_version(mut self, version: IpVersion) -> Self {
+        self.inner = self.inner.ip_version(version);
+        self
+    }
+
+    /// Largest width or height accepted for resizes (10000 by default)
+    ///
+    /// Resize options exceeding it fail locally with [`TinifyError::InvalidDimensions`]
+    /// before any request is made. Lower it to protect downstream systems from
+    /// oversized images.
+    pub fn max_dimension(mut self, max: u32) -> Self {
+        self.inner = self.inner.max_dimension(max);
+        self
+    }
+
+    pub fn retry_config(mut self, config: RetryConfig) -> Self {
+        self.inner = self.inner.retry_config(config);
+        self
+    }
+
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.inner = self.inner.rate_limit(limit);
+        self
+    }
+
+    /// Timer used to wait between retry attempts (defaults to [`TokioSleeper`])
+    ///
+    /// See [`Sleeper`]. Useful for driving backoff from another runtime's timer, or a
+    /// fake clock in tests.
+    pub fn sleeper<S: Sleeper + 'static>(mut self, sleeper: S) -> Self {
+        self.inner = self.inner.sleeper(sleeper);
+        self
+    }
+
+    /// Monthly compression limit used by [`on_quota_threshold`](Self::on_quota_threshold)
+    ///
+    /// When unset, the limit reported in the `Compression-Limit` response header is used.
+    pub fn monthly_compression_limit(mut self, limit: u32) -> Self {
+        self.inner = self.inner.monthly_compression_limit(limit);
+        self
+    }
+
+    /// Call `callback(count, limit)` once the `Compression-Count` reported by the API
+    /// reaches `fraction` of the monthly limit (e.g. `0.8` for 80%)
+    ///
+    /// The callback fires at most once per client, shared across clones. The limit
+    /// comes from [`monthly_compression_limit`](Self::monthly_compression_limit) or
+    /// the `Compression-Limit` header; nothing fires if neither is known. `build()`
+    /// rejects a fraction that is not a positive number.
+    pub fn on_quota_threshold<F>(mut self, fraction: f64, callback: F) -> Self
+    where
+        F: Fn(u32, u32) + Send + Sync + 'static,
+    {
+        self.inner = self.inner.on_quota_threshold(fraction, callback);
+        self
+    }
+
+    pub fn max_retry_attempts(mut self, attempts: u32) -> Self {
         self.inner = self.inner.max_retry_attempts(attempts);
         self
     }
@@ -357,12 +1378,92 @@ impl TinifyBuilder {
         self
     }
 
+    /// Set how many requests may be made in a burst before the per-minute rate applies
+    pub fn burst_capacity(mut self, burst: u32) -> Self {
+        self.inner = self.inner.burst_capacity(burst);
+        self
+    }
+
+    /// Share the rate limiter with every client built from `registry` using the same API key
+    ///
+    /// See [`RateLimiterRegistry`] for the tradeoffs.
+    pub fn rate_limiter_registry(mut self, registry: &RateLimiterRegistry) -> Self {
+        self.inner = self.inner.rate_limiter_registry(registry);
+        self
+    }
+
+    /// Apply every field set in `config`, leaving unset fields untouched
+    ///
+    /// Later builder calls still override values taken from the config.
+    pub fn from_config(mut self, config: TinifyConfig) -> Self {
+        if let Some(key) = config.api_key {
+            self = self.api_key(key);
+        }
+        if let Some(identifier) = config.app_identifier {
+            self = self.app_identifier(identifier);
+        }
+        if let Some(url) = config.base_url {
+            self = self.base_url(url);
+        }
+        if let Some(timeout) = config.timeout {
+            self = self.timeout(timeout);
+        }
+        if let Some(timeout) = config.download_timeout {
+            self = self.download_timeout(timeout);
+        }
+        if let Some(retry) = config.retry {
+            self = self.retry_config(retry);
+        }
+        if let Some(limit) = config.rate_limit {
+            self = self.rate_limit(limit);
+        }
+        if let Some(max) = config.max_dimension {
+            self = self.max_dimension(max);
+        }
+        if let Some(enabled) = config.decompress {
+            self = self.decompress(enabled);
+        }
+        if let Some(enabled) = config.dry_run {
+            self = self.dry_run(enabled);
+        }
+        if let Some(enabled) = config.strict {
+            self = self.strict(enabled);
+        }
+        self
+    }
+
     pub fn build(self) -> Result<Tinify> {
-        let client = self.inner.build()?;
+        #[cfg(feature = "fs")]
+        let inner = match self.api_key_file {
+            Some(path) => self.inner.api_key(Self::read_api_key_file(&path)?),
+            None => self.inner,
+        };
+        #[cfg(not(feature = "fs"))]
+        let inner = self.inner;
+
+        let client = inner.build()?;
         Ok(Tinify {
             client: Arc::new(client),
         })
     }
+
+    #[cfg(feature = "fs")]
+    fn read_api_key_file(path: &Path) -> Result<String> {
+        let contents = std::fs::read_to_string(path).map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => TinifyError::FileNotFound {
+                path: path.to_path_buf(),
+            },
+            _ => TinifyError::IoError(err),
+        })?;
+
+        let key = contents.trim();
+        if key.is_empty() {
+            return Err(TinifyError::InvalidOptions {
+                message: format!("API key file {} is empty", path.display()),
+            });
+        }
+        Ok(key.to_string())
+    }
 }
 
 impl Default for TinifyBuilder {
@@ -374,7 +1475,9 @@ impl Default for TinifyBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
     use std::env;
+    #[cfg(feature = "fs")]
     use tempfile::NamedTempFile;
     use tracing_test::traced_test;
 
@@ -410,6 +1513,237 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_config_round_trip() {
+        let config = TinifyConfig {
+            api_key: Some("config-key".to_string()),
+            app_identifier: Some("ConfigApp/1.0".to_string()),
+            timeout: Some(std::time::Duration::from_millis(2500)),
+            retry: Some(RetryConfig {
+                max_attempts: 2,
+                base_delay: std::time::Duration::from_millis(50),
+                ..Default::default()
+            }),
+            rate_limit: Some(RateLimit {
+                requests_per_minute: 30,
+                burst_capacity: 5,
+            }),
+            max_dimension: Some(4000),
+            dry_run: Some(true),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["timeout"], 2500);
+        assert_eq!(json["retry"]["base_delay"], 50);
+        assert_eq!(json["retry"]["max_delay"], 10_000);
+
+        let config: TinifyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(config.timeout, Some(std::time::Duration::from_millis(2500)));
+        let retry = config.retry.as_ref().unwrap();
+        assert_eq!(retry.max_attempts, 2);
+        assert_eq!(retry.base_delay, std::time::Duration::from_millis(50));
+
+        let client = Tinify::builder().from_config(config).build().unwrap();
+        assert_eq!(client.api_key(), "config-key");
+        assert_eq!(client.client.max_dimension(), 4000);
+        assert!(client.client.is_dry_run());
+
+        // Partial configs fall back to defaults, and are still validated on build
+        let config: TinifyConfig =
+            serde_json::from_str(r#"{"api_key":"k","retry":{"max_attempts":0}}"#).unwrap();
+        let result = Tinify::builder().from_config(config).build();
+        assert!(matches!(result, Err(TinifyError::InvalidOptions { .. })));
+    }
+
+    #[test]
+    fn test_effective_config_accessors() {
+        let retry = RetryConfig {
+            max_attempts: 4,
+            base_delay: std::time::Duration::from_millis(250),
+            ..Default::default()
+        };
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .retry_config(retry)
+            .requests_per_minute(42)
+            .burst_capacity(0)
+            .build()
+            .unwrap();
+
+        assert_eq!(client.retry_config().max_attempts, 4);
+        assert_eq!(
+            client.retry_config().base_delay,
+            std::time::Duration::from_millis(250)
+        );
+        assert_eq!(client.rate_limit().requests_per_minute, 42);
+        // Zero is replaced by the default burst
+        assert_eq!(client.rate_limit().burst_capacity, 10);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_reader_with_len_sets_content_length() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .match_header("content-length", "5")
+            .match_header("transfer-encoding", mockito::Matcher::Missing)
+            .match_body("image")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let reader = std::io::Cursor::new(b"image trailing bytes".to_vec());
+        let source = client
+            .source_from_reader_with_len(reader, 5, "image/png")
+            .await
+            .unwrap();
+        assert!(source.location().ends_with("/output/abc"));
+        shrink.assert_async().await;
+
+        let too_large = client
+            .source_from_reader_with_len(tokio::io::empty(), MAX_FILE_SIZE + 1, "image/png")
+            .await;
+        assert!(matches!(
+            too_large,
+            Err(TinifyError::FileTooLarge { size, .. }) if size == MAX_FILE_SIZE + 1
+        ));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_custom_max_dimension() {
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url("http://127.0.0.1:1")
+            .dry_run(true)
+            .max_dimension(4096)
+            .build()
+            .unwrap();
+        let source = client.source_from_buffer(vec![1, 2, 3]).await.unwrap();
+
+        let err = source
+            .resize(ResizeOptions::max_width(5000))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TinifyError::InvalidDimensions {
+                width: Some(5000),
+                max: 4096,
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("4096"));
+
+        assert!(source.resize(ResizeOptions::max_width(4000)).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_local_address_binding() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .local_address(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST))
+            .build()
+            .unwrap();
+
+        client.source_from_buffer(vec![1, 2, 3]).await.unwrap();
+        shrink.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_source_original_dimensions() {
+        let mut server = mockito::Server::new_async().await;
+        let _sized = server
+            .mock("POST", "/shrink")
+            .match_body(vec![1, 2, 3])
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .with_header("Image-Width", "1024")
+            .with_header("Image-Height", "768")
+            .create_async()
+            .await;
+        let _unsized = server
+            .mock("POST", "/shrink")
+            .match_body(vec![4, 5, 6])
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/def", server.url()))
+            .create_async()
+            .await;
+        let _json = server
+            .mock("POST", "/shrink")
+            .match_body(vec![7, 8, 9])
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/ghi", server.url()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"input":{"size":3,"type":"image/png"},"output":{"size":2,"type":"image/png","width":640,"height":480,"ratio":0.66}}"#,
+            )
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let source = client.source_from_buffer(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(source.original_dimensions(), Some((1024, 768)));
+        assert_eq!(source.dimensions(), None);
+
+        let source = client.source_from_buffer(vec![4, 5, 6]).await.unwrap();
+        assert_eq!(source.original_dimensions(), None);
+
+        let source = client.source_from_buffer(vec![7, 8, 9]).await.unwrap();
+        assert_eq!(source.original_dimensions(), Some((640, 480)));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_connection_pool_options() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(std::time::Duration::from_secs(300))
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            client.source_from_buffer(vec![1, 2, 3]).await.unwrap();
+        }
+        shrink.assert_async().await;
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_invalid_api_key() {
@@ -417,6 +1751,93 @@ mod tests {
         assert!(matches!(result, Err(TinifyError::InvalidApiKey)));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_deadline_bounds_whole_operation() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(format!("http://{}", addr))
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .unwrap();
+        let started = std::time::Instant::now();
+        let deadline = started + std::time::Duration::from_millis(200);
+        let bounded = client.with_request_options(RequestOptions::new().deadline(deadline));
+
+        let result = bounded.source_from_buffer(b"image".to_vec()).await;
+        assert!(matches!(result, Err(TinifyError::Timeout { .. })));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // Shorter than the ping's own timeout, which the deadline must cut short
+        let started = std::time::Instant::now();
+        let deadline = started + std::time::Duration::from_millis(200);
+        let bounded = client.with_request_options(RequestOptions::new().deadline(deadline));
+
+        let result = bounded.ping().await;
+        assert!(matches!(result, Err(TinifyError::Timeout { .. })));
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_api_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tinify_key");
+
+        std::fs::write(&path, "file-key\n").unwrap();
+        let client = Tinify::builder().api_key_file(&path).build().unwrap();
+        assert_eq!(client.api_key(), "file-key");
+
+        std::fs::write(&path, "  \n").unwrap();
+        assert!(matches!(
+            Tinify::builder().api_key_file(&path).build(),
+            Err(TinifyError::InvalidOptions { message }) if message.contains("is empty")
+        ));
+
+        let missing = dir.path().join("missing");
+        assert!(matches!(
+            Tinify::builder().api_key_file(&missing).build(),
+            Err(TinifyError::FileNotFound { path }) if path == missing
+        ));
+    }
+
+    #[test]
+    fn test_from_env() {
+        // Looked up from a map rather than the process environment, which other
+        // tests read concurrently
+        let from_vars = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            Tinify::from_vars(|name| vars.get(name).cloned())
+        };
+
+        assert!(matches!(from_vars(&[]), Err(TinifyError::InvalidApiKey)));
+        assert!(matches!(
+            from_vars(&[("TINIFY_API_KEY", "  ")]),
+            Err(TinifyError::InvalidApiKey)
+        ));
+        assert!(matches!(
+            from_vars(&[("TINIFY_API_KEY", "env-key"), ("TINIFY_RPM", "fast")]),
+            Err(TinifyError::InvalidOptions { .. })
+        ));
+
+        let client = from_vars(&[("TINIFY_API_KEY", "env-key"), ("TINIFY_RPM", "50")]).unwrap();
+        assert_eq!(client.api_key(), "env-key");
+    }
+
+    #[cfg(feature = "fs")]
     #[tokio::test]
     #[traced_test]
     async fn test_file_not_found() {
@@ -426,6 +1847,7 @@ mod tests {
         assert!(matches!(result, Err(TinifyError::FileNotFound { .. })));
     }
 
+    #[cfg(feature = "fs")]
     #[tokio::test]
     #[traced_test]
     async fn test_unsupported_format() {
@@ -437,6 +1859,111 @@ mod tests {
         assert!(matches!(result, Err(TinifyError::UnsupportedFormat { .. })));
     }
 
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    #[traced_test]
+    async fn test_compress_file_to_s3_and_gcs() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .expect(2)
+            .create_async()
+            .await;
+        let s3 = server
+            .mock("POST", "/output/abc")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "store": { "service": "s3", "path": "bucket/photo.png" }
+            })))
+            .with_status(200)
+            .with_header("Location", "https://bucket.s3.amazonaws.com/photo.png")
+            .create_async()
+            .await;
+        let gcs = server
+            .mock("POST", "/output/abc")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "store": { "service": "gcs", "path": "bucket/photo.png" }
+            })))
+            .with_status(200)
+            .with_header(
+                "Location",
+                "https://storage.googleapis.com/bucket/photo.png",
+            )
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let file = NamedTempFile::with_suffix(".png").unwrap();
+        std::fs::write(file.path(), b"image").unwrap();
+
+        let options = S3Options::new(
+            "key".to_string(),
+            "secret".to_string(),
+            "us-east-1".to_string(),
+            "bucket/photo.png".to_string(),
+        );
+        let location = client
+            .compress_file_to_s3(file.path(), options)
+            .await
+            .unwrap();
+        assert_eq!(location, "https://bucket.s3.amazonaws.com/photo.png");
+
+        let options = GCSOptions::new("token".to_string(), "bucket/photo.png".to_string());
+        let location = client
+            .compress_file_to_gcs(file.path(), options)
+            .await
+            .unwrap();
+        assert_eq!(location, "https://storage.googleapis.com/bucket/photo.png");
+
+        // Invalid destinations are rejected before uploading
+        let options = GCSOptions::new("token".to_string(), "no-key".to_string());
+        let result = client.compress_file_to_gcs(file.path(), options).await;
+        assert!(matches!(result, Err(TinifyError::InvalidOptions { .. })));
+
+        shrink.assert_async().await;
+        s3.assert_async().await;
+        gcs.assert_async().await;
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    #[traced_test]
+    async fn test_animated_gif_passthrough() {
+        use std::io::Write;
+
+        // GIF89a header with a NETSCAPE2.0 looping extension and two frame markers
+        let mut gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00".to_vec();
+        gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+        gif.extend_from_slice(b"\x2c{frame-1}\x2c{frame-2}\x3b");
+
+        let mut temp_file = NamedTempFile::with_suffix(".gif").unwrap();
+        temp_file.write_all(&gif).unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .match_body(gif.clone())
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        assert!(Tinify::validate_image_format("animation.GIF").is_ok());
+        client.source_from_file(temp_file.path()).await.unwrap();
+        shrink.assert_async().await;
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_buffer_too_large() {
@@ -447,6 +1974,37 @@ mod tests {
         assert!(matches!(result, Err(TinifyError::FileTooLarge { .. })));
     }
 
+    #[tokio::test]
+    #[traced_test]
+    async fn test_empty_buffer_rejected_locally() {
+        // Nothing listens on this address, so reaching the network would fail differently
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url("http://127.0.0.1:1")
+            .max_retry_attempts(1)
+            .build()
+            .unwrap();
+
+        let result = client.source_from_buffer(Vec::new()).await;
+        assert!(matches!(result, Err(TinifyError::EmptyInput)));
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    #[traced_test]
+    async fn test_empty_file_rejected_locally() {
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url("http://127.0.0.1:1")
+            .max_retry_attempts(1)
+            .build()
+            .unwrap();
+
+        let temp_file = NamedTempFile::with_suffix(".png").unwrap();
+        let result = client.source_from_file(temp_file.path()).await;
+        assert!(matches!(result, Err(TinifyError::EmptyInput)));
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_invalid_url() {
@@ -456,7 +2014,496 @@ mod tests {
         assert!(matches!(result, Err(TinifyError::UrlParseError(_))));
     }
 
+    #[test]
+    fn test_parse_data_uri() {
+        let (mime, bytes) = Tinify::parse_data_uri("data:image/png;base64,iVBORw0KGgo=").unwrap();
+        assert_eq!(mime.essence_str(), "image/png");
+        assert_eq!(bytes, b"\x89PNG\r\n\x1a\n");
+
+        let result = Tinify::parse_data_uri("data:text/html;base64,PGh0bWw+");
+        assert!(matches!(result, Err(TinifyError::UnsupportedFormat { .. })));
+
+        let result = Tinify::parse_data_uri("data:image/png,rawdata");
+        assert!(matches!(result, Err(TinifyError::InvalidDataUri { .. })));
+
+        let result = Tinify::parse_data_uri("image/png;base64,iVBORw0KGgo=");
+        assert!(matches!(result, Err(TinifyError::InvalidDataUri { .. })));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_data_uri_round_trip() {
+        let png = b"\x89PNG\r\n\x1a\nfake-image-data".to_vec();
+        let data_uri = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&png)
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .match_body(png.clone())
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let output = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .with_header("Content-Type", "image/png")
+            .with_body(png.clone())
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let source = client.source_from_data_uri(&data_uri).await.unwrap();
+        let mut result = source.resize(ResizeOptions::default()).await.unwrap();
+
+        assert_eq!(result.to_data_uri().await.unwrap(), data_uri);
+        shrink.assert_async().await;
+        output.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_buffer_with_content_type() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .match_header("Content-Type", "image/webp")
+            .match_body("{not-json")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        client
+            .source_from_buffer_with_type(b"{not-json".to_vec(), "image/webp")
+            .await
+            .unwrap();
+        shrink.assert_async().await;
+
+        let result = client
+            .source_from_buffer_with_type(b"data".to_vec(), "not a mime")
+            .await;
+        assert!(matches!(result, Err(TinifyError::UnsupportedFormat { .. })));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_stats_count_requests_and_bytes() {
+        let mut server = mockito::Server::new_async().await;
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let _output = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .with_body("resized-image")
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        assert_eq!(client.stats(), ClientStats::default());
+
+        let source = client.source_from_buffer(vec![0u8; 100]).await.unwrap();
+        let mut result = source.resize(ResizeOptions::fit(10, 10)).await.unwrap();
+        result.to_buffer().await.unwrap();
+
+        let body = serde_json::to_vec(&json!({ "resize": ResizeOptions::fit(10, 10) })).unwrap();
+        let stats = client.stats();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.retries, 0);
+        assert_eq!(stats.bytes_up, 100 + body.len() as u64);
+        assert_eq!(stats.bytes_down, "resized-image".len() as u64);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_source_dispatches_image_input() {
+        let mut server = mockito::Server::new_async().await;
+        let mut shrink = |matcher: mockito::Matcher, header: Option<&str>| {
+            let mut mock = server.mock("POST", "/shrink").match_body(matcher);
+            if let Some(content_type) = header {
+                mock = mock.match_header("Content-Type", content_type);
+            }
+            mock.with_status(201)
+                .with_header("Location", "https://api.tinify.com/output/abc")
+                .expect(1)
+                .create()
+        };
+        let buffer = shrink("buffer".into(), None);
+        let url = shrink(
+            mockito::Matcher::Json(json!({ "source": { "url": "https://example.com/a.png" } })),
+            Some("application/json"),
+        );
+        let reader = shrink("reader".into(), Some("image/png"));
+        #[cfg(feature = "fs")]
+        let file = shrink("file".into(), None);
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        client
+            .source(ImageInput::Buffer(b"buffer".to_vec()))
+            .await
+            .unwrap();
+        client
+            .source(ImageInput::Url("https://example.com/a.png".to_string()))
+            .await
+            .unwrap();
+        client
+            .source(ImageInput::Reader {
+                reader: Box::new(&b"reader"[..]),
+                content_type: "image/png".to_string(),
+            })
+            .await
+            .unwrap();
+        #[cfg(feature = "fs")]
+        {
+            use std::io::Write;
+            let mut temp_file = NamedTempFile::with_suffix(".png").unwrap();
+            temp_file.write_all(b"file").unwrap();
+            client
+                .source(ImageInput::File(temp_file.path().to_path_buf()))
+                .await
+                .unwrap();
+            file.assert();
+        }
+
+        buffer.assert();
+        url.assert();
+        reader.assert();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_compress_buffer_keeps_smaller_original() {
+        let mut server = mockito::Server::new_async().await;
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let _output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("larger than the input")
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let original = b"small input".to_vec();
+        let compressed = client.compress_buffer(original.clone(), 0.0).await.unwrap();
+        assert!(compressed.skipped);
+        assert_eq!(compressed.data, original);
+        assert_eq!(compressed.compressed_size, 21);
+
+        let large = vec![0u8; 100];
+        let compressed = client.compress_buffer(large, 0.5).await.unwrap();
+        assert!(!compressed.skipped);
+        assert_eq!(compressed.data, b"larger than the input");
+
+        assert!(matches!(
+            client.compress_buffer(original, 1.5).await,
+            Err(TinifyError::InvalidOptions { .. })
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    #[traced_test]
+    async fn test_file_if_beneficial_reports_already_optimized() {
+        let mut server = mockito::Server::new_async().await;
+        let _shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .create_async()
+            .await;
+        let _output = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("optimized png!")
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("optimized.png");
+        std::fs::write(&path, b"optimized png").unwrap();
+
+        let result = client
+            .source_from_file_if_beneficial(&path, 0.05)
+            .await
+            .unwrap();
+        assert!(result.skipped);
+        assert_eq!(result.data, b"optimized png");
+        assert_eq!(result.original_size, 13);
+        assert_eq!(result.compressed_size, 14);
+
+        assert!(matches!(
+            client
+                .source_from_file_if_beneficial(dir.path().join("missing.png"), 0.05)
+                .await,
+            Err(TinifyError::FileNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_malformed_base64() {
+        let client = Tinify::new(get_test_api_key()).unwrap();
+
+        let result = client.source_from_base64("not*valid*base64").await;
+        assert!(matches!(result, Err(TinifyError::Base64Error(_))));
+
+        let result = client
+            .source_from_data_uri("data:image/png;base64,not*valid*base64")
+            .await;
+        assert!(matches!(result, Err(TinifyError::Base64Error(_))));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_url_scheme_validation() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .match_body(r#"{"source":{"url":"https://example.com/image.png"}}"#)
+            .with_status(201)
+            .with_header("Location", &format!("{}/output/abc", server.url()))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        for url in ["file:///etc/passwd", "ftp://example.com/image.png"] {
+            let result = client.source_from_url(url).await;
+            assert!(
+                matches!(result, Err(TinifyError::InvalidUrl { .. })),
+                "{url}"
+            );
+        }
+
+        let source = client
+            .source_from_url("https://example.com/image.png")
+            .await
+            .unwrap();
+        assert_eq!(source.location(), format!("{}/output/abc", server.url()));
+        shrink.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_source_from_url_rejects_non_images() {
+        let mut server = mockito::Server::new_async().await;
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(415)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"error":"Unsupported media type","message":"File type is not supported."}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        // Rejected locally by extension, without contacting the API
+        match client
+            .source_from_url("https://example.com/index.html")
+            .await
+        {
+            Err(TinifyError::UnsupportedFormat { format }) => assert_eq!(format, "html"),
+            other => panic!("expected UnsupportedFormat, got {other:?}"),
+        }
+
+        // Other URLs are sent, and a non-image response is reported the same way
+        match client.source_from_url("https://example.com/page").await {
+            Err(TinifyError::UnsupportedFormat { format }) => assert_eq!(format, "unknown"),
+            other => panic!("expected UnsupportedFormat, got {other:?}"),
+        }
+
+        // Unfamiliar extensions are left for the API to judge
+        match client
+            .source_from_url("https://example.com/photo.avif")
+            .await
+        {
+            Err(TinifyError::UnsupportedFormat { format }) => assert_eq!(format, "avif"),
+            other => panic!("expected UnsupportedFormat, got {other:?}"),
+        }
+        shrink.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_result_to_source_avoids_reupload() {
+        let mut server = mockito::Server::new_async().await;
+        let location = format!("{}/output/abc", server.url());
+        let shrink = server
+            .mock("POST", "/shrink")
+            .with_status(201)
+            .with_header("Location", &location)
+            .expect(2)
+            .create_async()
+            .await;
+        let resize = server
+            .mock("POST", "/output/abc")
+            .match_body(mockito::Matcher::Json(
+                json!({ "resize": { "method": "scale", "width": 100 } }),
+            ))
+            .with_status(200)
+            .with_body("resized")
+            .expect(2)
+            .create_async()
+            .await;
+        let convert = server
+            .mock("POST", "/output/abc")
+            .match_body(mockito::Matcher::Json(
+                json!({ "convert": { "type": "image/webp" } }),
+            ))
+            .with_status(200)
+            .with_body("converted")
+            .expect(1)
+            .create_async()
+            .await;
+        let combined = server
+            .mock("POST", "/output/abc")
+            .match_body(mockito::Matcher::Json(json!({
+                "resize": { "method": "scale", "width": 100 },
+                "convert": { "type": "image/webp" },
+            })))
+            .with_status(200)
+            .with_body("combined")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+        let source = client.source_from_buffer(b"image".to_vec()).await.unwrap();
+        let resize_options = ResizeOptions::scale_width(100);
+        let webp = ConvertOptions::new(ImageFormat::WebP);
+
+        // Round trip: download, upload again, convert
+        let bytes = source
+            .resize(&resize_options)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reuploaded = client.source_from_buffer(bytes).await.unwrap();
+        let round_trip = reuploaded
+            .convert(&webp)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(round_trip, b"converted");
+        let requests_round_trip = client.stats().requests;
+        assert_eq!(requests_round_trip, 4);
+
+        // Location reuse: one request that combines both operations
+        let resized = source.resize(&resize_options).await;
+        let reused = resized.unwrap().to_source().unwrap();
+        let result = reused.convert(&webp).await.unwrap().bytes().await.unwrap();
+        assert_eq!(result, b"combined");
+        // The resize request is repeated here only to obtain the result; the conversion
+        // itself added one request and no upload
+        assert_eq!(client.stats().requests - requests_round_trip, 2);
+
+        shrink.assert_async().await;
+        resize.assert_async().await;
+        convert.assert_async().await;
+        combined.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_dry_run_validates_without_network() {
+        // Nothing listens on this address, so any real request would fail
+        let client = Tinify::builder()
+            .api_key("test-key")
+            .base_url("http://127.0.0.1:1")
+            .max_retry_attempts(1)
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        let source = client.source_from_buffer(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(source.location(), "http://127.0.0.1:1/output/dry-run");
+
+        let mut result = source.resize(ResizeOptions::fit(100, 100)).await.unwrap();
+        assert!(result.to_buffer().await.unwrap().is_empty());
+        assert!(source.to_buffer().await.unwrap().is_empty());
+
+        // Local validation still applies
+        assert!(matches!(
+            source.resize(ResizeOptions::fit(0, 100)).await,
+            Err(TinifyError::InvalidDimensions { .. })
+        ));
+        assert!(matches!(
+            client.source_from_url("file:///etc/passwd").await,
+            Err(TinifyError::InvalidUrl { .. })
+        ));
+        assert!(matches!(
+            client
+                .source_from_buffer(vec![0u8; (MAX_FILE_SIZE + 1) as usize])
+                .await,
+            Err(TinifyError::FileTooLarge { .. })
+        ));
+    }
+
     // Skip integration tests if no real API key is provided
+    #[cfg(feature = "fs")]
     #[tokio::test]
     #[traced_test]
     async fn test_integration_from_file() {
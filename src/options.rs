@@ -1,4 +1,7 @@
+use crate::error::{Result, TinifyError};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ResizeMethod {
@@ -12,6 +15,14 @@ pub enum ResizeMethod {
     Thumb,
 }
 
+/// Resize options
+///
+/// [`Scale`](ResizeMethod::Scale) and [`Fit`](ResizeMethod::Fit) keep the aspect
+/// ratio; [`Cover`](ResizeMethod::Cover) and [`Thumb`](ResizeMethod::Thumb) produce
+/// exactly the requested size. `Scale` takes a width or a height but not both, see
+/// [`scale_width`](Self::scale_width) and [`scale_height`](Self::scale_height); the
+/// other methods take both. Use [`fit`](Self::fit) to bound both dimensions while
+/// keeping the aspect ratio.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResizeOptions {
     pub method: ResizeMethod,
@@ -21,6 +32,75 @@ pub struct ResizeOptions {
     pub height: Option<u32>,
 }
 
+impl ResizeOptions {
+    /// Limit the width to `width`, keeping the aspect ratio
+    ///
+    /// Equivalent to [`scale_width`](Self::scale_width).
+    pub fn max_width(width: u32) -> Self {
+        Self::scale_width(width)
+    }
+
+    /// Limit the height to `height`, keeping the aspect ratio
+    ///
+    /// Equivalent to [`scale_height`](Self::scale_height).
+    pub fn max_height(height: u32) -> Self {
+        Self::scale_height(height)
+    }
+
+    /// Scale the image down proportionally to the given width
+    ///
+    /// The height is calculated automatically to keep the aspect ratio.
+    pub fn scale_width(width: u32) -> Self {
+        Self {
+            method: ResizeMethod::Scale,
+            width: Some(width),
+            height: None,
+        }
+    }
+
+    /// Scale the image down proportionally to the given height
+    ///
+    /// The width is calculated automatically to keep the aspect ratio. `Scale` takes
+    /// a single dimension; to scale into a `width` x `height` box while keeping the
+    /// aspect ratio, use [`fit`](Self::fit).
+    pub fn scale_height(height: u32) -> Self {
+        Self {
+            method: ResizeMethod::Scale,
+            width: None,
+            height: Some(height),
+        }
+    }
+
+    /// Scale the image down proportionally so it fits within `width` x `height`
+    pub fn fit(width: u32, height: u32) -> Self {
+        Self {
+            method: ResizeMethod::Fit,
+            width: Some(width),
+            height: Some(height),
+        }
+    }
+
+    /// Scale and crop the image to exactly `width` x `height`, using intelligent
+    /// detection of the area of interest
+    pub fn cover(width: u32, height: u32) -> Self {
+        Self {
+            method: ResizeMethod::Cover,
+            width: Some(width),
+            height: Some(height),
+        }
+    }
+
+    /// Create a thumbnail of exactly `width` x `height`, detecting cut-out images
+    /// and adding background space where needed
+    pub fn thumb(width: u32, height: u32) -> Self {
+        Self {
+            method: ResizeMethod::Thumb,
+            width: Some(width),
+            height: Some(height),
+        }
+    }
+}
+
 impl Default for ResizeOptions {
     fn default() -> Self {
         Self {
@@ -31,7 +111,11 @@ impl Default for ResizeOptions {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Output format for conversions
+///
+/// GIF is accepted as an input format but the API cannot convert to it, so it has no
+/// variant here; see [`InputFormat`] for the formats that can be uploaded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ImageFormat {
     #[serde(rename = "image/avif")]
     Avif,
@@ -43,14 +127,196 @@ pub enum ImageFormat {
     Png,
 }
 
+impl ImageFormat {
+    /// MIME type of the format, e.g. `"image/png"`
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Avif => "image/avif",
+            ImageFormat::WebP => "image/webp",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+        }
+    }
+
+    /// Canonical file extension of the format, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Avif => "avif",
+            ImageFormat::WebP => "webp",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+        }
+    }
+
+    /// Map a file extension (case-insensitive, with or without a leading dot) to a format
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::ImageFormat;
+    ///
+    /// assert_eq!(ImageFormat::from_extension("JPEG"), Some(ImageFormat::Jpeg));
+    /// assert_eq!(ImageFormat::from_extension(".webp"), Some(ImageFormat::WebP));
+    /// assert_eq!(ImageFormat::from_extension("bmp"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension
+            .trim_start_matches('.')
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "avif" => Some(ImageFormat::Avif),
+            "webp" => Some(ImageFormat::WebP),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// Map a MIME type (ignoring parameters such as `; charset=...`) to a format
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "image/avif" => Some(ImageFormat::Avif),
+            "image/webp" => Some(ImageFormat::WebP),
+            "image/jpeg" | "image/jpg" => Some(ImageFormat::Jpeg),
+            "image/png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+}
+
+/// Format of image data that can be uploaded for compression
+///
+/// Every [`ImageFormat`] can be uploaded, plus GIF, which can be compressed but not
+/// converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputFormat {
+    Avif,
+    WebP,
+    Jpeg,
+    Png,
+    Gif,
+}
+
+impl InputFormat {
+    /// MIME type of the format, e.g. `"image/gif"`
+    pub fn mime_type(&self) -> &'static str {
+        match self.output_format() {
+            Some(format) => format.mime_type(),
+            None => "image/gif",
+        }
+    }
+
+    /// The same format as a conversion target, or `None` for GIF
+    pub fn output_format(&self) -> Option<ImageFormat> {
+        match self {
+            InputFormat::Avif => Some(ImageFormat::Avif),
+            InputFormat::WebP => Some(ImageFormat::WebP),
+            InputFormat::Jpeg => Some(ImageFormat::Jpeg),
+            InputFormat::Png => Some(ImageFormat::Png),
+            InputFormat::Gif => None,
+        }
+    }
+
+    /// Detect the format of image data from its leading magic bytes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::{ImageFormat, InputFormat};
+    ///
+    /// let gif = InputFormat::from_magic_bytes(b"GIF89a\x01\x00").unwrap();
+    /// assert_eq!(gif, InputFormat::Gif);
+    /// assert_eq!(gif.output_format(), None);
+    /// assert_eq!(
+    ///     InputFormat::from_magic_bytes(b"\x89PNG\r\n\x1a\n").and_then(|f| f.output_format()),
+    ///     Some(ImageFormat::Png)
+    /// );
+    /// ```
+    pub fn from_magic_bytes(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(InputFormat::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(InputFormat::Jpeg)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(InputFormat::Gif)
+        } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(InputFormat::WebP)
+        } else if data.len() >= 12
+            && &data[4..8] == b"ftyp"
+            && matches!(&data[8..12], b"avif" | b"avis")
+        {
+            Some(InputFormat::Avif)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<ImageFormat> for InputFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Avif => InputFormat::Avif,
+            ImageFormat::WebP => InputFormat::WebP,
+            ImageFormat::Jpeg => InputFormat::Jpeg,
+            ImageFormat::Png => InputFormat::Png,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConvertOptions {
     #[serde(rename = "type")]
     pub format: ImageFormat,
+    /// `#RGB` or `#RRGGBB` hex color, or the keywords `"white"` and `"black"` that
+    /// the API also accepts
     #[serde(skip_serializing_if = "Option::is_none")]
     pub background: Option<String>,
 }
 
+impl ConvertOptions {
+    /// Convert to the given format without a background color
+    pub fn new(format: ImageFormat) -> Self {
+        Self {
+            format,
+            background: None,
+        }
+    }
+
+    /// Set the background color used to fill transparent areas
+    ///
+    /// Takes a `#RGB` or `#RRGGBB` hex color, or `"white"` or `"black"`, which the
+    /// API accepts as well. Other values are rejected by [`Source::convert`] before
+    /// any request is sent.
+    ///
+    /// [`Source::convert`]: crate::Source::convert
+    pub fn background<S: Into<String>>(mut self, color: S) -> Self {
+        self.background = Some(color.into());
+        self
+    }
+}
+
+/// Check that `color` is a background the API understands: `#RGB`, `#RRGGBB`,
+/// `white` or `black`
+pub(crate) fn validate_background(color: &str) -> Result<()> {
+    if color == "white" || color == "black" {
+        return Ok(());
+    }
+
+    let valid_hex = color.strip_prefix('#').is_some_and(|hex| {
+        (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+
+    if !valid_hex {
+        return Err(TinifyError::InvalidBackground {
+            value: color.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PreserveMetadata {
     #[serde(rename = "copyright")]
@@ -66,6 +332,118 @@ pub struct PreserveOptions {
     pub preserve: Vec<PreserveMetadata>,
 }
 
+/// Resize, convert and preserve metadata in a single request
+///
+/// Sent by [`Source::transform`](crate::Source::transform). Combining operations
+/// avoids re-uploading intermediate images: converting and then preserving metadata
+/// on a re-uploaded copy costs two compressions, while one transform with both counts
+/// once. Preserving metadata never counts as a compression by itself.
+///
+/// # Examples
+///
+/// ```
+/// use tinify::{ConvertOptions, ImageFormat, PreserveMetadata, TransformOptions};
+///
+/// let options = TransformOptions::new()
+///     .convert(ConvertOptions::new(ImageFormat::WebP))
+///     .preserve(vec![PreserveMetadata::Copyright]);
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransformOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resize: Option<ResizeOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convert: Option<ConvertOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve: Option<Vec<PreserveMetadata>>,
+}
+
+impl TransformOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn resize(mut self, options: ResizeOptions) -> Self {
+        self.resize = Some(options);
+        self
+    }
+
+    pub fn convert(mut self, options: ConvertOptions) -> Self {
+        self.convert = Some(options);
+        self
+    }
+
+    pub fn preserve(mut self, metadata: Vec<PreserveMetadata>) -> Self {
+        self.preserve = Some(metadata);
+        self
+    }
+}
+
+/// Canned ACL applied to an object stored on S3
+///
+/// Serializes to the canonical S3 ACL strings, e.g. `"public-read"`. Use
+/// [`S3Acl::Custom`] for values without a variant, such as ACLs specific to an
+/// S3-compatible service. Converting from a string maps canonical names to their
+/// variant and anything else to `Custom`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum S3Acl {
+    Private,
+    PublicRead,
+    PublicReadWrite,
+    AuthenticatedRead,
+    AwsExecRead,
+    BucketOwnerRead,
+    BucketOwnerFullControl,
+    Custom(String),
+}
+
+impl S3Acl {
+    /// ACL string sent to the API, e.g. `"public-read"`
+    pub fn as_str(&self) -> &str {
+        match self {
+            S3Acl::Private => "private",
+            S3Acl::PublicRead => "public-read",
+            S3Acl::PublicReadWrite => "public-read-write",
+            S3Acl::AuthenticatedRead => "authenticated-read",
+            S3Acl::AwsExecRead => "aws-exec-read",
+            S3Acl::BucketOwnerRead => "bucket-owner-read",
+            S3Acl::BucketOwnerFullControl => "bucket-owner-full-control",
+            S3Acl::Custom(acl) => acl,
+        }
+    }
+}
+
+impl From<&str> for S3Acl {
+    fn from(acl: &str) -> Self {
+        match acl {
+            "private" => S3Acl::Private,
+            "public-read" => S3Acl::PublicRead,
+            "public-read-write" => S3Acl::PublicReadWrite,
+            "authenticated-read" => S3Acl::AuthenticatedRead,
+            "aws-exec-read" => S3Acl::AwsExecRead,
+            "bucket-owner-read" => S3Acl::BucketOwnerRead,
+            "bucket-owner-full-control" => S3Acl::BucketOwnerFullControl,
+            other => S3Acl::Custom(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for S3Acl {
+    fn from(acl: String) -> Self {
+        S3Acl::from(acl.as_str())
+    }
+}
+
+impl From<S3Acl> for String {
+    fn from(acl: S3Acl) -> Self {
+        match acl {
+            S3Acl::Custom(acl) => acl,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct S3Options {
     pub service: String, // Always "s3"
@@ -73,10 +451,15 @@ pub struct S3Options {
     pub aws_secret_access_key: String,
     pub region: String,
     pub path: String,
+    /// Extra headers for the stored object, shared rather than copied when the
+    /// options are cloned
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub headers: Option<serde_json::Value>,
+    pub headers: Option<Arc<serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub acl: Option<String>,
+    pub acl: Option<S3Acl>,
+    /// Endpoint URL of an S3-compatible service; `None` means AWS S3
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
 }
 
 impl S3Options {
@@ -94,8 +477,123 @@ impl S3Options {
             path,
             headers: None,
             acl: None,
+            endpoint: None,
         }
     }
+
+    /// Options for Cloudflare R2
+    ///
+    /// Sets the account's R2 endpoint (`https://<account_id>.r2.cloudflarestorage.com`)
+    /// and the `auto` region R2 expects. `path` is `bucket/key`, as for S3.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::S3Options;
+    ///
+    /// let options = S3Options::r2("0123abcd", "access-key", "secret", "images/photo.png");
+    /// assert_eq!(options.region, "auto");
+    /// ```
+    pub fn r2(
+        account_id: impl Into<String>,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        path: impl Into<String>,
+    ) -> Self {
+        let mut options = Self::new(
+            access_key_id.into(),
+            secret_access_key.into(),
+            "auto".to_string(),
+            path.into(),
+        );
+        options.endpoint = Some(format!(
+            "https://{}.r2.cloudflarestorage.com",
+            account_id.into()
+        ));
+        options
+    }
+
+    /// Create an [`S3OptionsBuilder`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinify::S3Options;
+    ///
+    /// let options = S3Options::builder()
+    ///     .access_key_id("AKIA...")
+    ///     .secret_access_key("secret")
+    ///     .region("us-east-1")
+    ///     .path("bucket/images/photo.png")
+    ///     .acl("public-read")
+    ///     .build()?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// ```
+    pub fn builder() -> S3OptionsBuilder {
+        S3OptionsBuilder::default()
+    }
+}
+
+/// Builder for [`S3Options`] that checks all required fields are set
+#[derive(Debug, Clone, Default)]
+pub struct S3OptionsBuilder {
+    aws_access_key_id: Option<String>,
+    aws_secret_access_key: Option<String>,
+    region: Option<String>,
+    path: Option<String>,
+    headers: Option<Arc<serde_json::Value>>,
+    acl: Option<S3Acl>,
+    endpoint: Option<String>,
+}
+
+impl S3OptionsBuilder {
+    pub fn access_key_id<S: Into<String>>(mut self, key: S) -> Self {
+        self.aws_access_key_id = Some(key.into());
+        self
+    }
+
+    pub fn secret_access_key<S: Into<String>>(mut self, secret: S) -> Self {
+        self.aws_secret_access_key = Some(secret.into());
+        self
+    }
+
+    pub fn region<S: Into<String>>(mut self, region: S) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn headers<H: Into<Arc<serde_json::Value>>>(mut self, headers: H) -> Self {
+        self.headers = Some(headers.into());
+        self
+    }
+
+    pub fn acl<A: Into<S3Acl>>(mut self, acl: A) -> Self {
+        self.acl = Some(acl.into());
+        self
+    }
+
+    pub fn endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn build(self) -> Result<S3Options> {
+        let mut options = S3Options::new(
+            required(self.aws_access_key_id, "aws_access_key_id")?,
+            required(self.aws_secret_access_key, "aws_secret_access_key")?,
+            required(self.region, "region")?,
+            required(self.path, "path")?,
+        );
+        options.headers = self.headers;
+        options.acl = self.acl;
+        options.endpoint = self.endpoint;
+        Ok(options)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,8 +601,10 @@ pub struct GCSOptions {
     pub service: String, // Always "gcs"
     pub gcp_access_token: String,
     pub path: String,
+    /// Extra headers for the stored object, shared rather than copied when the
+    /// options are cloned
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub headers: Option<serde_json::Value>,
+    pub headers: Option<Arc<serde_json::Value>>,
 }
 
 impl GCSOptions {
@@ -116,15 +616,550 @@ impl GCSOptions {
             headers: None,
         }
     }
+
+    /// Create a [`GCSOptionsBuilder`]
+    pub fn builder() -> GCSOptionsBuilder {
+        GCSOptionsBuilder::default()
+    }
+}
+
+const GCS_METADATA_PREFIX: &str = "X-Goog-Meta-";
+
+/// Custom object metadata for Google Cloud Storage
+///
+/// GCS reads object metadata from `X-Goog-Meta-*` headers. Keys are prefixed
+/// automatically (a key that already carries the prefix is kept as is) and are
+/// checked to be valid header names when the options are built. Pass to
+/// [`GCSOptionsBuilder::metadata`].
+///
+/// # Examples
+///
+/// ```
+/// use tinify::{GCSOptions, GcsMetadata};
+///
+/// let options = GCSOptions::builder()
+///     .access_token("token")
+///     .path("bucket/photo.png")
+///     .metadata(GcsMetadata::new().insert("owner", "web-team"))
+///     .build()?;
+/// assert_eq!(options.headers.unwrap()["X-Goog-Meta-owner"], "web-team");
+/// # Ok::<(), tinify::TinifyError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcsMetadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl GcsMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a metadata entry, replacing any previous value for `key`
+    pub fn insert<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.entries.insert(key.into(), value.into());
+        self
+    }
+
+    /// Header names and values to send, with keys prefixed by `X-Goog-Meta-`
+    pub fn to_headers(&self) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let mut headers = serde_json::Map::new();
+        for (key, value) in &self.entries {
+            let name = match key.get(..GCS_METADATA_PREFIX.len()) {
+                Some(prefix) if prefix.eq_ignore_ascii_case(GCS_METADATA_PREFIX) => key.clone(),
+                _ => format!("{}{}", GCS_METADATA_PREFIX, key),
+            };
+            if name.len() == GCS_METADATA_PREFIX.len() || !name.bytes().all(is_header_name_byte) {
+                return Err(TinifyError::InvalidOptions {
+                    message: format!("invalid GCS metadata key `{}`", key),
+                });
+            }
+            if value.chars().any(char::is_control) {
+                return Err(TinifyError::InvalidOptions {
+                    message: format!(
+                        "GCS metadata value for `{}` contains control characters",
+                        key
+                    ),
+                });
+            }
+            headers.insert(name, serde_json::Value::String(value.clone()));
+        }
+        Ok(headers)
+    }
+}
+
+/// Whether `byte` may appear in an HTTP header name (an RFC 9110 `tchar`)
+fn is_header_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
 }
 
+/// Builder for [`GCSOptions`] that checks all required fields are set
+#[derive(Debug, Clone, Default)]
+pub struct GCSOptionsBuilder {
+    gcp_access_token: Option<String>,
+    path: Option<String>,
+    headers: Option<Arc<serde_json::Value>>,
+    metadata: Option<GcsMetadata>,
+}
+
+impl GCSOptionsBuilder {
+    pub fn access_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.gcp_access_token = Some(token.into());
+        self
+    }
+
+    pub fn path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn headers<H: Into<Arc<serde_json::Value>>>(mut self, headers: H) -> Self {
+        self.headers = Some(headers.into());
+        self
+    }
+
+    /// Object metadata, merged into [`headers`](Self::headers) on build
+    pub fn metadata(mut self, metadata: GcsMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    pub fn build(self) -> Result<GCSOptions> {
+        let mut options = GCSOptions::new(
+            required(self.gcp_access_token, "gcp_access_token")?,
+            required(self.path, "path")?,
+        );
+        options.headers = match (self.headers.map(Arc::unwrap_or_clone), self.metadata) {
+            (headers, None) => headers.map(Arc::new),
+            (None, Some(metadata)) => Some(Arc::new(metadata.to_headers()?.into())),
+            (Some(serde_json::Value::Object(mut headers)), Some(metadata)) => {
+                headers.extend(metadata.to_headers()?);
+                Some(Arc::new(headers.into()))
+            }
+            (Some(_), Some(_)) => {
+                return Err(TinifyError::InvalidOptions {
+                    message: "GCS headers must be a JSON object to add metadata".to_string(),
+                });
+            }
+        };
+        Ok(options)
+    }
+}
+
+fn required(value: Option<String>, field: &str) -> Result<String> {
+    value
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| TinifyError::InvalidOptions {
+            message: format!("missing required field `{}`", field),
+        })
+}
+
+/// Destination for [`Source::store`](crate::Source::store)
+///
+/// Serialized as the bare options object, e.g. `{"service": "s3", ...}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum StoreOptions {
     S3(S3Options),
     GCS(GCSOptions),
+    /// Raw `store` object sent verbatim, for providers or options this crate doesn't
+    /// model yet. Must be a JSON object with a `service` key.
+    Custom(serde_json::Value),
+}
+
+impl StoreOptions {
+    /// Check the options before they are sent
+    ///
+    /// S3 and GCS paths must be `bucket/key` with both parts non-empty, and a
+    /// [`StoreOptions::Custom`] payload must be an object naming its service.
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self {
+            StoreOptions::S3(options) => validate_store_path(&options.path),
+            StoreOptions::GCS(options) => validate_store_path(&options.path),
+            StoreOptions::Custom(value) => {
+                let has_service = value
+                    .as_object()
+                    .and_then(|object| object.get("service"))
+                    .is_some_and(|service| service.is_string());
+                if !has_service {
+                    return Err(TinifyError::InvalidOptions {
+                        message:
+                            "custom store options must be a JSON object with a string `service` key"
+                                .to_string(),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn validate_store_path(path: &str) -> Result<()> {
+    match path.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(()),
+        _ => Err(TinifyError::InvalidOptions {
+            message: format!(
+                "store path `{}` must be `bucket/key` with a non-empty bucket and key",
+                path
+            ),
+        }),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoreRequest {
     pub store: StoreOptions,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_background() {
+        for color in ["#FFF", "#ffffff", "#00aBcD", "white", "black"] {
+            assert!(validate_background(color).is_ok(), "{color}");
+        }
+
+        for color in ["FFFFFF", "#GGG", "#FFFF", "#1234567", "red", "White", ""] {
+            assert!(
+                matches!(
+                    validate_background(color),
+                    Err(TinifyError::InvalidBackground { .. })
+                ),
+                "{color}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_store_path_validation() {
+        let s3 = |path: &str| {
+            StoreOptions::S3(S3Options::new(
+                "key".to_string(),
+                "secret".to_string(),
+                "us-east-1".to_string(),
+                path.to_string(),
+            ))
+        };
+        let gcs =
+            |path: &str| StoreOptions::GCS(GCSOptions::new("token".to_string(), path.to_string()));
+
+        for path in ["bucket/photo.png", "bucket/nested/dir/photo.png"] {
+            assert!(s3(path).validate().is_ok(), "{path}");
+            assert!(gcs(path).validate().is_ok(), "{path}");
+        }
+        for path in ["justbucket", "/bucket/photo.png", "bucket/", "", "/"] {
+            assert!(
+                matches!(s3(path).validate(), Err(TinifyError::InvalidOptions { .. })),
+                "{path}"
+            );
+            assert!(
+                matches!(
+                    gcs(path).validate(),
+                    Err(TinifyError::InvalidOptions { .. })
+                ),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_r2_options() {
+        // Each argument may be a different string type
+        let options = S3Options::r2(
+            String::from("0123abcd"),
+            "key".to_string(),
+            "secret",
+            format!("images/{}", "photo.png"),
+        );
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({
+                "service": "s3",
+                "aws_access_key_id": "key",
+                "aws_secret_access_key": "secret",
+                "region": "auto",
+                "path": "images/photo.png",
+                "endpoint": "https://0123abcd.r2.cloudflarestorage.com",
+            })
+        );
+    }
+
+    #[test]
+    fn test_transform_serialization() {
+        let options = TransformOptions::new()
+            .resize(ResizeOptions::scale_width(800))
+            .convert(ConvertOptions::new(ImageFormat::Png).background("white"))
+            .preserve(vec![
+                PreserveMetadata::Copyright,
+                PreserveMetadata::Location,
+            ]);
+
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({
+                "resize": { "method": "scale", "width": 800 },
+                "convert": { "type": "image/png", "background": "white" },
+                "preserve": ["copyright", "location"],
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(TransformOptions::new()).unwrap(),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn test_resize_constructors() {
+        assert_eq!(
+            serde_json::to_value(ResizeOptions::scale_width(800)).unwrap(),
+            json!({ "method": "scale", "width": 800 })
+        );
+        assert_eq!(
+            serde_json::to_value(ResizeOptions::max_width(800)).unwrap(),
+            json!({ "method": "scale", "width": 800 })
+        );
+        assert_eq!(
+            serde_json::to_value(ResizeOptions::max_height(600)).unwrap(),
+            json!({ "method": "scale", "height": 600 })
+        );
+        assert_eq!(
+            serde_json::to_value(ResizeOptions::scale_height(600)).unwrap(),
+            json!({ "method": "scale", "height": 600 })
+        );
+        assert_eq!(
+            serde_json::to_value(ResizeOptions::cover(300, 200)).unwrap(),
+            json!({ "method": "cover", "width": 300, "height": 200 })
+        );
+        assert_eq!(
+            serde_json::to_value(ConvertOptions::new(ImageFormat::Jpeg).background("#FFFFFF"))
+                .unwrap(),
+            json!({ "type": "image/jpeg", "background": "#FFFFFF" })
+        );
+    }
+
+    #[test]
+    fn test_store_builders() {
+        let s3 = S3Options::builder()
+            .access_key_id("key")
+            .secret_access_key("secret")
+            .region("us-west-1")
+            .path("bucket/photo.png")
+            .acl("no-acl")
+            .build()
+            .unwrap();
+        assert_eq!(s3.service, "s3");
+        assert_eq!(s3.acl, Some(S3Acl::Custom("no-acl".to_string())));
+
+        let missing = S3Options::builder().access_key_id("key").build();
+        assert!(matches!(
+            missing,
+            Err(TinifyError::InvalidOptions { message }) if message.contains("aws_secret_access_key")
+        ));
+
+        let gcs = GCSOptions::builder()
+            .access_token("token")
+            .path("bucket/photo.png")
+            .build()
+            .unwrap();
+        assert_eq!(gcs.service, "gcs");
+        assert!(GCSOptions::builder()
+            .path("bucket/photo.png")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_gcs_metadata_headers() {
+        let options = GCSOptions::builder()
+            .access_token("token")
+            .path("bucket/photo.png")
+            .headers(serde_json::json!({ "Cache-Control": "public, max-age=31536000" }))
+            .metadata(
+                GcsMetadata::new()
+                    .insert("owner", "web-team")
+                    .insert("X-Goog-Meta-build", "42"),
+            )
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_value(StoreOptions::GCS(options)).unwrap();
+        assert_eq!(
+            json["headers"],
+            serde_json::json!({
+                "Cache-Control": "public, max-age=31536000",
+                "X-Goog-Meta-build": "42",
+                "X-Goog-Meta-owner": "web-team",
+            })
+        );
+
+        for key in ["", "has space", "ünicode", "X-Goog-Meta-"] {
+            let result = GCSOptions::builder()
+                .access_token("token")
+                .path("bucket/photo.png")
+                .metadata(GcsMetadata::new().insert(key, "value"))
+                .build();
+            assert!(
+                matches!(result, Err(TinifyError::InvalidOptions { .. })),
+                "{key:?}"
+            );
+        }
+        assert!(GcsMetadata::new()
+            .insert("owner", "line\r\nbreak")
+            .to_headers()
+            .is_err());
+    }
+
+    #[test]
+    fn test_cloning_options_shares_headers() {
+        let headers: serde_json::Map<_, _> = (0..10_000)
+            .map(|i| (format!("X-Amz-Meta-{i}"), json!("value")))
+            .collect();
+        let options = S3Options::builder()
+            .access_key_id("key")
+            .secret_access_key("secret")
+            .region("us-east-1")
+            .path("bucket/photo.png")
+            .headers(serde_json::Value::Object(headers))
+            .build()
+            .unwrap();
+
+        // Cloning bumps a reference count instead of copying the header map
+        let destinations = vec![StoreOptions::S3(options.clone()); 100];
+        let original = options.headers.as_ref().unwrap();
+        for destination in &destinations {
+            let StoreOptions::S3(clone) = destination else {
+                unreachable!()
+            };
+            assert!(Arc::ptr_eq(original, clone.headers.as_ref().unwrap()));
+        }
+        assert_eq!(Arc::strong_count(original), 101);
+
+        let json = serde_json::to_value(&destinations[0]).unwrap();
+        assert_eq!(json["headers"]["X-Amz-Meta-42"], "value");
+    }
+
+    #[test]
+    fn test_s3_acl_serde() {
+        let cases = [
+            (S3Acl::Private, "private"),
+            (S3Acl::PublicRead, "public-read"),
+            (S3Acl::PublicReadWrite, "public-read-write"),
+            (S3Acl::AuthenticatedRead, "authenticated-read"),
+            (S3Acl::AwsExecRead, "aws-exec-read"),
+            (S3Acl::BucketOwnerRead, "bucket-owner-read"),
+            (S3Acl::BucketOwnerFullControl, "bucket-owner-full-control"),
+            (
+                S3Acl::Custom("log-delivery-write".to_string()),
+                "log-delivery-write",
+            ),
+        ];
+        for (acl, expected) in cases {
+            assert_eq!(serde_json::to_value(&acl).unwrap(), json!(expected));
+            assert_eq!(
+                serde_json::from_value::<S3Acl>(json!(expected)).unwrap(),
+                acl
+            );
+        }
+
+        // A typo is not mistaken for a known ACL
+        assert_eq!(
+            S3Acl::from("public_read"),
+            S3Acl::Custom("public_read".to_string())
+        );
+    }
+
+    #[test]
+    fn test_store_options_serialization() {
+        let s3 = StoreOptions::S3(S3Options::new(
+            "key".to_string(),
+            "secret".to_string(),
+            "us-west-1".to_string(),
+            "bucket/photo.png".to_string(),
+        ));
+        assert_eq!(
+            serde_json::to_value(&s3).unwrap(),
+            json!({
+                "service": "s3",
+                "aws_access_key_id": "key",
+                "aws_secret_access_key": "secret",
+                "region": "us-west-1",
+                "path": "bucket/photo.png",
+            })
+        );
+
+        let payload = json!({
+            "service": "azure",
+            "account": "images",
+            "container": "thumbs",
+            "path": "photo.png",
+        });
+        let custom = StoreOptions::Custom(payload.clone());
+        assert!(custom.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(StoreRequest { store: custom }).unwrap(),
+            json!({ "store": payload })
+        );
+
+        for invalid in [
+            json!({ "path": "photo.png" }),
+            json!("s3"),
+            json!({ "service": 1 }),
+        ] {
+            assert!(matches!(
+                StoreOptions::Custom(invalid).validate(),
+                Err(TinifyError::InvalidOptions { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_image_format_mapping() {
+        for (ext, format) in [
+            ("png", ImageFormat::Png),
+            ("jpg", ImageFormat::Jpeg),
+            ("JPEG", ImageFormat::Jpeg),
+            ("webp", ImageFormat::WebP),
+            ("avif", ImageFormat::Avif),
+        ] {
+            assert_eq!(ImageFormat::from_extension(ext), Some(format.clone()));
+            assert_eq!(
+                ImageFormat::from_mime(format.mime_type()),
+                Some(format.clone())
+            );
+            assert_eq!(
+                ImageFormat::from_extension(format.extension()),
+                Some(format)
+            );
+        }
+
+        assert_eq!(ImageFormat::from_extension("tiff"), None);
+        assert_eq!(ImageFormat::from_mime("text/html; charset=utf-8"), None);
+        assert_eq!(
+            InputFormat::from_magic_bytes(b"\x89PNG\r\n\x1a\n...."),
+            Some(InputFormat::Png)
+        );
+        assert_eq!(
+            InputFormat::from_magic_bytes(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(InputFormat::WebP)
+        );
+        assert_eq!(InputFormat::from_magic_bytes(b"<html>"), None);
+    }
+
+    #[test]
+    fn test_gif_magic_bytes() {
+        for header in [&b"GIF87a\x01\x00"[..], b"GIF89a\x01\x00\x01\x00\x80"] {
+            let format = InputFormat::from_magic_bytes(header).unwrap();
+            assert_eq!(format, InputFormat::Gif);
+            assert_eq!(format.mime_type(), "image/gif");
+            assert_eq!(format.output_format(), None);
+        }
+        assert_eq!(InputFormat::from_magic_bytes(b"GIF8"), None);
+        assert_eq!(
+            InputFormat::from(ImageFormat::Jpeg).output_format(),
+            Some(ImageFormat::Jpeg)
+        );
+    }
+}
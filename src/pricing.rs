@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+/// Price of the compressions falling into one band of a [`Plan`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingTier {
+    /// Last compression of the month, inclusive, billed at this price; `None` for
+    /// the open-ended top tier
+    pub up_to: Option<u32>,
+    /// Price of each compression in this tier, in USD
+    pub price_per_compression: f64,
+}
+
+/// Monthly pricing of the Tinify API as a list of graduated tiers
+///
+/// Each compression is billed at the price of the tier its position in the month
+/// falls into, so the first tiers keep their price as usage grows. Compressions
+/// beyond the last tier are billed at the last tier's price. The built-in plans
+/// reflect published pricing at the time of writing; build a `Plan` from your own
+/// tiers if it has changed or you have a custom agreement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub tiers: Vec<PricingTier>,
+}
+
+impl Plan {
+    /// Free plan: 500 compressions per month, further requests are rejected
+    pub fn free() -> Self {
+        Self {
+            tiers: vec![PricingTier {
+                up_to: None,
+                price_per_compression: 0.0,
+            }],
+        }
+    }
+
+    /// Pay-as-you-go: 500 free compressions, then $0.009 each up to 10,000 and
+    /// $0.002 each beyond
+    pub fn pay_as_you_go() -> Self {
+        Self {
+            tiers: vec![
+                PricingTier {
+                    up_to: Some(500),
+                    price_per_compression: 0.0,
+                },
+                PricingTier {
+                    up_to: Some(10_000),
+                    price_per_compression: 0.009,
+                },
+                PricingTier {
+                    up_to: None,
+                    price_per_compression: 0.002,
+                },
+            ],
+        }
+    }
+
+    /// Estimated cost in USD of `count` compressions in one month
+    pub fn estimate_cost(&self, count: u32) -> f64 {
+        let mut cost = 0.0;
+        let mut billed = 0;
+        for (i, tier) in self.tiers.iter().enumerate() {
+            let is_last = i + 1 == self.tiers.len();
+            let upper = match tier.up_to {
+                Some(up_to) if !is_last => up_to.min(count),
+                _ => count,
+            };
+            if upper > billed {
+                cost += f64::from(upper - billed) * tier.price_per_compression;
+                billed = upper;
+            }
+            if billed >= count {
+                break;
+            }
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_free_tier() {
+        assert_close(Plan::free().estimate_cost(500), 0.0);
+        assert_close(Plan::pay_as_you_go().estimate_cost(0), 0.0);
+        assert_close(Plan::pay_as_you_go().estimate_cost(500), 0.0);
+        assert_close(Plan::pay_as_you_go().estimate_cost(501), 0.009);
+    }
+
+    #[test]
+    fn test_paid_tier_boundary() {
+        let plan = Plan::pay_as_you_go();
+        assert_close(plan.estimate_cost(10_000), 9_500.0 * 0.009);
+        assert_close(plan.estimate_cost(10_001), 9_500.0 * 0.009 + 0.002);
+        assert_close(
+            plan.estimate_cost(20_000),
+            9_500.0 * 0.009 + 10_000.0 * 0.002,
+        );
+    }
+
+    #[test]
+    fn test_custom_plan() {
+        let plan = Plan {
+            tiers: vec![
+                PricingTier {
+                    up_to: Some(100),
+                    price_per_compression: 0.01,
+                },
+                PricingTier {
+                    up_to: Some(200),
+                    price_per_compression: 0.005,
+                },
+            ],
+        };
+        // Usage past the last tier keeps its price
+        assert_close(plan.estimate_cost(300), 1.0 + 200.0 * 0.005);
+    }
+}
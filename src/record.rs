@@ -0,0 +1,114 @@
+use reqwest::{header::HeaderMap, RequestBuilder, Response};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+const REDACTED: &str = "[redacted]";
+
+/// One HTTP request and its outcome, captured in record mode
+///
+/// Only metadata is captured, never bodies. The `Authorization` header, which
+/// carries the API key, is redacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedExchange {
+    pub method: String,
+    pub url: String,
+    pub request_headers: BTreeMap<String, String>,
+    /// Status of the response, `None` if no response was received
+    pub status: Option<u16>,
+    pub response_headers: BTreeMap<String, String>,
+    /// Transport error, if the request failed before a response arrived
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Destination for exchanges captured in record mode
+///
+/// Implemented for closures, so `|exchange: &RecordedExchange| ...` can be passed
+/// to [`TinifyBuilder::record_sink`](crate::TinifyBuilder::record_sink) directly.
+pub trait RecordSink: Send + Sync {
+    fn record(&self, exchange: &RecordedExchange);
+}
+
+impl<F> RecordSink for F
+where
+    F: Fn(&RecordedExchange) + Send + Sync,
+{
+    fn record(&self, exchange: &RecordedExchange) {
+        self(exchange)
+    }
+}
+
+/// Sink that emits every exchange as a JSON `info` event on the `tinify::record` target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingSink;
+
+impl RecordSink for TracingSink {
+    fn record(&self, exchange: &RecordedExchange) {
+        match serde_json::to_string(exchange) {
+            Ok(json) => info!(target: "tinify::record", exchange = %json, "Recorded exchange"),
+            Err(err) => info!(target: "tinify::record", "Failed to serialize exchange: {}", err),
+        }
+    }
+}
+
+/// Sends requests and hands each exchange to the configured sink
+#[derive(Clone)]
+pub(crate) struct Recorder(Arc<dyn RecordSink>);
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Recorder")
+    }
+}
+
+impl Recorder {
+    pub(crate) fn new(sink: Arc<dyn RecordSink>) -> Self {
+        Self(sink)
+    }
+
+    pub(crate) async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+
+        let mut exchange = RecordedExchange {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            request_headers: header_map(request.headers()),
+            status: None,
+            response_headers: BTreeMap::new(),
+            error: None,
+            elapsed_ms: 0,
+        };
+
+        let started = Instant::now();
+        let result = client.execute(request).await;
+        exchange.elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(response) => {
+                exchange.status = Some(response.status().as_u16());
+                exchange.response_headers = header_map(response.headers());
+            }
+            Err(err) => exchange.error = Some(err.to_string()),
+        }
+
+        self.0.record(&exchange);
+        result
+    }
+}
+
+fn header_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == reqwest::header::AUTHORIZATION {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
@@ -1,6 +1,16 @@
-use crate::error::Result;
-use reqwest::Response;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::client::RequestId;
+use crate::client::StatsCounters;
+use crate::error::{Result, TinifyError};
+use crate::options::ImageFormat;
+use crate::source::Source;
+use base64::Engine;
+use futures_util::StreamExt;
+use md5::{Digest, Md5};
+use reqwest::{header::HeaderMap, Response};
+#[cfg(feature = "fs")]
 use std::path::Path;
+use std::sync::Arc;
 
 /// Represents the result of Tinify API operations
 ///
@@ -8,21 +18,147 @@ use std::path::Path;
 /// and can be used to retrieve processed image data, metadata information, etc.
 #[derive(Debug)]
 pub struct TinifyResult {
+    headers: HeaderMap,
+    request_id: Option<String>,
+    stats: Option<Arc<StatsCounters>>,
+    counted_as_compression: bool,
+    source: Option<Source>,
     response: Option<Response>,
 }
 
+/// Request id the client tagged `response` with
+#[cfg(not(target_arch = "wasm32"))]
+fn request_id(response: &Response) -> Option<String> {
+    response
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+}
+
+/// Responses have no extensions on wasm32, so the request id is not available
+#[cfg(target_arch = "wasm32")]
+fn request_id(_response: &Response) -> Option<String> {
+    None
+}
+
+/// Stats counters the body of `response` counts toward
+#[cfg(not(target_arch = "wasm32"))]
+fn stats(response: &Response) -> Option<Arc<StatsCounters>> {
+    response.extensions().get::<Arc<StatsCounters>>().cloned()
+}
+
+/// Responses have no extensions on wasm32, so downloads are not counted
+#[cfg(target_arch = "wasm32")]
+fn stats(_response: &Response) -> Option<Arc<StatsCounters>> {
+    None
+}
+
 impl TinifyResult {
     /// Create a new TinifyResult object
     ///
+    /// The response headers are captured up front for every kind of operation, so
+    /// metadata such as [`compression_count`](Self::compression_count) and
+    /// [`location`](Self::location) is available even when the body is empty, as it
+    /// is for store responses.
+    ///
     /// # Arguments
     ///
     /// * `response` - HTTP response object
     pub fn new(response: Response) -> Self {
         Self {
+            headers: response.headers().clone(),
+            request_id: request_id(&response),
+            stats: stats(&response),
+            counted_as_compression: false,
+            source: None,
             response: Some(response),
         }
     }
 
+    /// Mark whether the operation that produced this result is billed as a compression
+    pub(crate) fn counted(mut self, counted: bool) -> Self {
+        self.counted_as_compression = counted;
+        self
+    }
+
+    /// Remember the source and operations that produced this result
+    pub(crate) fn with_source(mut self, source: Source) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Get a [`Source`] for applying further operations without re-uploading
+    ///
+    /// The returned source points at the same upload and carries the operations that
+    /// produced this result, so adding e.g. a conversion sends both in one request on
+    /// the original compressed image. This avoids downloading the bytes and passing
+    /// them to [`Tinify::source_from_buffer`](crate::Tinify::source_from_buffer), which
+    /// costs an extra upload and compresses an already compressed image again. An
+    /// operation of the same kind replaces the recorded one rather than stacking, so
+    /// resizing twice needs a re-upload of the downloaded bytes.
+    ///
+    /// Returns `None` for results not produced by a [`Source`], e.g. ones created
+    /// with [`TinifyResult::new`]. Storing is not carried over.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ConvertOptions, ImageFormat, ResizeOptions, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let resized = source.resize(ResizeOptions::scale_width(800)).await?;
+    /// let webp = resized
+    ///     .to_source()
+    ///     .expect("result of a source operation")
+    ///     .convert(ConvertOptions::new(ImageFormat::WebP))
+    ///     .await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub fn to_source(&self) -> Option<Source> {
+        self.source.clone()
+    }
+
+    /// Whether the operation that produced this result counted toward the quota
+    ///
+    /// Tinify bills resizing and format conversion as one additional compression
+    /// each time they are requested, while preserving metadata and storing to cloud
+    /// storage are free. This is derived from the operations that were sent, not from
+    /// the server: it is `true` if they included a resize or a conversion. The upload
+    /// itself is billed when the [`Source`](crate::Source) is created and is not
+    /// reflected here, so downloading the plain compressed image returns `false`.
+    pub fn counted_as_compression(&self) -> bool {
+        self.counted_as_compression
+    }
+
+    /// Get a response header by name
+    ///
+    /// Headers are captured when the result is created, so they remain available
+    /// after the image data has been downloaded.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(value)` if the header is present and is valid UTF-8, otherwise returns `None`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// Get the request id
+    ///
+    /// Returns the `X-Request-Id` sent with the request that produced this result, if
+    /// request ids are enabled via `TinifyBuilder::send_request_id`. Log it alongside
+    /// the outcome to correlate with server-side logs.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    fn parse_header<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.header(name).and_then(|s| s.trim().parse().ok())
+    }
+
     /// Get image data to memory buffer
     ///
     /// Read the image data from the response into a byte array.
@@ -36,7 +172,7 @@ impl TinifyResult {
     ///
     /// let client = Tinify::new("your-api-key".to_string())?;
     /// let source = client.source_from_file("input.png").await?;
-    /// let mut result = source.resize(Default::default()).await?;
+    /// let mut result = source.resize(tinify::ResizeOptions::default()).await?;
     ///
     /// let image_data = result.to_buffer().await?;
     /// println!("Image size: {} bytes", image_data.len());
@@ -45,11 +181,170 @@ impl TinifyResult {
     /// ```
     pub async fn to_buffer(&mut self) -> Result<Vec<u8>> {
         // Since reqwest::Response can only be consumed once, we use take() to move out the response
-        let response = self.response.take().expect("Response has been consumed");
+        let response = self
+            .response
+            .take()
+            .ok_or_else(|| TinifyError::UnknownError {
+                message: "Response body has already been consumed".to_string(),
+                status: None,
+            })?;
         let bytes = response.bytes().await?;
+        if let Some(stats) = &self.stats {
+            stats.add_bytes_down(bytes.len());
+        }
         Ok(bytes.to_vec())
     }
 
+    /// Download the image data into a caller-provided buffer
+    ///
+    /// Clears `buf` and fills it from the response stream, reusing its capacity
+    /// instead of allocating a new `Vec` like [`TinifyResult::to_buffer`] does. Useful
+    /// for servers that pool buffers across downloads. On error `buf` holds whatever
+    /// was received before the failure.
+    /// Note: This method consumes the response data and can only be called once.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let mut buf = Vec::with_capacity(1024 * 1024);
+    /// for path in ["a.png", "b.png"] {
+    ///     let mut result = client.source_from_file(path).await?.result().await?;
+    ///     result.read_into(&mut buf).await?;
+    ///     println!("{}: {} bytes", path, buf.len());
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn read_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        let response = self
+            .response
+            .take()
+            .ok_or_else(|| TinifyError::UnknownError {
+                message: "Response body has already been consumed".to_string(),
+                status: None,
+            })?;
+
+        buf.clear();
+        if let Some(len) = response.content_length() {
+            buf.reserve(len as usize);
+        }
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if let Some(stats) = &self.stats {
+                stats.add_bytes_down(chunk.len());
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+
+    /// Get image data to memory buffer, verifying its integrity
+    ///
+    /// Like [`TinifyResult::to_buffer`], but when the response carries a `Content-MD5`
+    /// header, or an `ETag` that is a plain MD5 hex digest, the MD5 of the downloaded
+    /// body is compared against it. If neither header is usable, this behaves exactly
+    /// like `to_buffer`.
+    /// Note: This method consumes the response data and can only be called once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::ChecksumMismatch`] if the digests differ.
+    pub async fn to_buffer_checked(&mut self) -> Result<Vec<u8>> {
+        let bytes = self.to_buffer().await?;
+        let digest = Md5::digest(&bytes);
+
+        if let Some(expected) = self.header("Content-MD5") {
+            let actual = base64::engine::general_purpose::STANDARD.encode(digest);
+            if expected.trim() != actual {
+                return Err(TinifyError::ChecksumMismatch {
+                    expected: expected.trim().to_string(),
+                    actual,
+                });
+            }
+        } else if let Some(etag) = self.header("ETag") {
+            let etag = etag.trim().trim_start_matches("W/").trim_matches('"');
+            // Multipart or opaque ETags are not content digests and can't be checked
+            if etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()) {
+                let actual: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+                if !etag.eq_ignore_ascii_case(&actual) {
+                    return Err(TinifyError::ChecksumMismatch {
+                        expected: etag.to_string(),
+                        actual,
+                    });
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Consume the result and download the image data
+    ///
+    /// Equivalent to [`TinifyResult::to_buffer`] but takes ownership, which makes it
+    /// convenient for passing the bytes straight into another call.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Tinify, ConvertOptions, ImageFormat};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    /// let result = source.convert(ConvertOptions::new(ImageFormat::WebP)).await?;
+    ///
+    /// let webp_source = client.source_from_buffer(result.bytes().await?).await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn bytes(mut self) -> Result<Vec<u8>> {
+        self.to_buffer().await
+    }
+
+    /// Get image data as a base64 string
+    ///
+    /// Downloads the image data via [`TinifyResult::to_buffer`] and encodes it
+    /// using the standard base64 alphabet.
+    /// Note: This method consumes the response data and can only be called once.
+    pub async fn to_base64(&mut self) -> Result<String> {
+        let bytes = self.to_buffer().await?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    /// Get image data as a `data:` URI
+    ///
+    /// Downloads the image data and encodes it as `data:<content-type>;base64,...`,
+    /// suitable for embedding directly into HTML or JSON.
+    /// Note: This method consumes the response data and can only be called once.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::Tinify;
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    /// let mut result = source.resize(tinify::ResizeOptions::default()).await?;
+    ///
+    /// let data_uri = result.to_data_uri().await?;
+    /// println!("<img src=\"{}\">", data_uri);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn to_data_uri(&mut self) -> Result<String> {
+        let content_type = self
+            .content_type()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let encoded = self.to_base64().await?;
+        Ok(format!("data:{};base64,{}", content_type, encoded))
+    }
+
     /// Save image to local file
     ///
     /// Save the image data from the response to the specified local file path.
@@ -67,19 +362,28 @@ impl TinifyResult {
     ///
     /// let client = Tinify::new("your-api-key".to_string())?;
     /// let source = client.source_from_file("input.png").await?;
-    /// let mut result = source.resize(Default::default()).await?;
+    /// let mut result = source.resize(tinify::ResizeOptions::default()).await?;
     ///
     /// result.to_file("output.png").await?;
     /// println!("Image saved to output.png");
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
+    #[cfg(feature = "fs")]
     pub async fn to_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let bytes = self.to_buffer().await?;
         tokio::fs::write(path, bytes).await?;
         Ok(())
     }
 
+    /// Get the stored object's location
+    ///
+    /// Returns the `Location` header, which after [`Source::store`] is the URL of
+    /// the image in cloud storage.
+    pub fn location(&self) -> Option<&str> {
+        self.header("Location")
+    }
+
     /// Get compression count
     ///
     /// Returns the compression count statistics for the current API key this month.
@@ -88,12 +392,37 @@ impl TinifyResult {
     ///
     /// Returns `Some(count)` if the response header contains compression count information, otherwise returns `None`.
     pub fn compression_count(&self) -> Option<u32> {
-        self.response
-            .as_ref()?
-            .headers()
-            .get("Compression-Count")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok())
+        self.parse_header("Compression-Count")
+    }
+
+    /// Get remaining compressions
+    ///
+    /// Returns the number of compressions left this month for the current API key.
+    /// This is read from a `Compression-Count-Remaining` header, or derived from
+    /// `Compression-Limit` minus `Compression-Count` when both are present. The public
+    /// Tinify API currently only sends `Compression-Count`, so this is usually `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(remaining)` if the response headers allow it to be determined, otherwise returns `None`.
+    pub fn compression_count_remaining(&self) -> Option<u32> {
+        self.parse_header("Compression-Count-Remaining")
+            .or_else(|| {
+                let limit = self.compression_limit()?;
+                Some(limit.saturating_sub(self.compression_count()?))
+            })
+    }
+
+    /// Get monthly compression limit
+    ///
+    /// Returns the monthly compression limit for the current API key if the response
+    /// includes a `Compression-Limit` header.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(limit)` if the response header contains limit information, otherwise returns `None`.
+    pub fn compression_limit(&self) -> Option<u32> {
+        self.parse_header("Compression-Limit")
     }
 
     /// Get image width
@@ -104,12 +433,7 @@ impl TinifyResult {
     ///
     /// Returns `Some(width)` if the response header contains image width information, otherwise returns `None`.
     pub fn image_width(&self) -> Option<u32> {
-        self.response
-            .as_ref()?
-            .headers()
-            .get("Image-Width")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok())
+        self.parse_header("Image-Width")
     }
 
     /// Get image height
@@ -120,12 +444,7 @@ impl TinifyResult {
     ///
     /// Returns `Some(height)` if the response header contains image height information, otherwise returns `None`.
     pub fn image_height(&self) -> Option<u32> {
-        self.response
-            .as_ref()?
-            .headers()
-            .get("Image-Height")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok())
+        self.parse_header("Image-Height")
     }
 
     /// Get content type
@@ -136,12 +455,38 @@ impl TinifyResult {
     ///
     /// Returns `Some(content_type)` if the response header contains content type information, otherwise returns `None`.
     pub fn content_type(&self) -> Option<String> {
-        self.response
-            .as_ref()?
-            .headers()
-            .get("Content-Type")
-            .and_then(|v| v.to_str().ok())
-            .map(String::from)
+        self.header("Content-Type").map(String::from)
+    }
+
+    /// Get the image format, derived from the `Content-Type` header
+    ///
+    /// Returns `None` if the header is missing or names a type without an
+    /// [`ImageFormat`] variant, such as GIF.
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.header("Content-Type").and_then(ImageFormat::from_mime)
+    }
+
+    /// Check that the result has the expected image format
+    ///
+    /// Compares the `Content-Type` header against `format`'s MIME type and returns
+    /// `self` for chaining, e.g. `result.expect_format(ImageFormat::WebP)?.to_file(path)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::UnsupportedFormat`] with the actual content type if it is
+    /// missing or doesn't match.
+    pub fn expect_format(&mut self, format: ImageFormat) -> Result<&mut Self> {
+        let content_type = self.header("Content-Type").unwrap_or_default();
+        if ImageFormat::from_mime(content_type) != Some(format) {
+            return Err(TinifyError::UnsupportedFormat {
+                format: if content_type.is_empty() {
+                    "missing content type".to_string()
+                } else {
+                    content_type.to_string()
+                },
+            });
+        }
+        Ok(self)
     }
 
     /// Get content length
@@ -152,22 +497,124 @@ impl TinifyResult {
     ///
     /// Returns `Some(length)` if the response header contains content length information, otherwise returns `None`.
     pub fn content_length(&self) -> Option<u64> {
-        self.response
-            .as_ref()?
-            .headers()
-            .get("Content-Length")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok())
+        self.parse_header("Content-Length")
     }
 }
 
-impl From<TinifyResult> for Vec<u8> {
-    fn from(mut result: TinifyResult) -> Self {
-        // This is a blocking conversion - in real usage, you should use to_buffer().await
-        // This implementation is primarily for examples where we need synchronous conversion
-        tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(async { result.to_buffer().await.unwrap_or_default() })
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn mock_result(headers: &[(&str, &str)], body: &'static [u8]) -> TinifyResult {
+        let mut builder = http::Response::builder().status(200);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        TinifyResult::new(Response::from(builder.body(body).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_read_into_reuses_buffer() {
+        let mut buf = Vec::with_capacity(1024);
+        let ptr = buf.as_ptr();
+
+        let mut first = mock_result(&[], b"first image");
+        first.read_into(&mut buf).await.unwrap();
+        assert_eq!(buf, b"first image");
+
+        let mut second = mock_result(&[], b"second");
+        second.read_into(&mut buf).await.unwrap();
+        assert_eq!(buf, b"second");
+        assert_eq!(buf.capacity(), 1024);
+        assert_eq!(buf.as_ptr(), ptr);
+
+        assert!(second.read_into(&mut buf).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expect_format() {
+        let mut result = mock_result(&[("Content-Type", "image/webp")], b"webp");
+        let data = result
+            .expect_format(ImageFormat::WebP)
+            .unwrap()
+            .to_buffer()
+            .await
+            .unwrap();
+        assert_eq!(data, b"webp");
+
+        let mut result = mock_result(&[("Content-Type", "image/png")], b"png");
+        match result.expect_format(ImageFormat::Avif) {
+            Err(TinifyError::UnsupportedFormat { format }) => assert_eq!(format, "image/png"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let mut result = mock_result(&[], b"");
+        assert!(matches!(
+            result.expect_format(ImageFormat::Png),
+            Err(TinifyError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quota_headers() {
+        let mut result = mock_result(
+            &[("Compression-Count", "120"), ("Compression-Limit", "500")],
+            b"image",
+        );
+        assert_eq!(result.compression_count(), Some(120));
+        assert_eq!(result.compression_limit(), Some(500));
+        assert_eq!(result.compression_count_remaining(), Some(380));
+
+        // Headers stay readable after the body has been consumed
+        assert_eq!(result.to_buffer().await.unwrap(), b"image");
+        assert_eq!(result.header("compression-count"), Some("120"));
+        assert!(matches!(
+            result.to_buffer().await,
+            Err(TinifyError::UnknownError { .. })
+        ));
+
+        let result = mock_result(
+            &[
+                ("Compression-Count", "120"),
+                ("Compression-Count-Remaining", "42"),
+            ],
+            b"",
+        );
+        assert_eq!(result.compression_count_remaining(), Some(42));
+        assert_eq!(result.compression_limit(), None);
+
+        let result = mock_result(&[("Compression-Count", "120")], b"");
+        assert_eq!(result.compression_count_remaining(), None);
+    }
+
+    #[tokio::test]
+    async fn test_to_buffer_checked() {
+        // MD5("image") = 78805a221a988e79ef3f42d7c5bfd418
+        let mut result = mock_result(&[("Content-MD5", "eIBaIhqYjnnvP0LXxb/UGA==")], b"image");
+        assert_eq!(result.to_buffer_checked().await.unwrap(), b"image");
+
+        let mut result = mock_result(
+            &[("ETag", "\"78805a221a988e79ef3f42d7c5bfd418\"")],
+            b"image",
+        );
+        assert_eq!(result.to_buffer_checked().await.unwrap(), b"image");
+
+        let mut result = mock_result(&[("Content-MD5", "AAAAAAAAAAAAAAAAAAAAAA==")], b"image");
+        assert!(matches!(
+            result.to_buffer_checked().await,
+            Err(TinifyError::ChecksumMismatch { .. })
+        ));
+
+        let mut result = mock_result(
+            &[("ETag", "\"00000000000000000000000000000000\"")],
+            b"image",
+        );
+        assert!(matches!(
+            result.to_buffer_checked().await,
+            Err(TinifyError::ChecksumMismatch { .. })
+        ));
+
+        let mut result = mock_result(&[], b"image");
+        assert_eq!(result.to_buffer_checked().await.unwrap(), b"image");
     }
 }
@@ -1,18 +1,68 @@
-use crate::client::Client;
+use crate::client::{Client, RequestBody};
 use crate::error::Result;
-use crate::options::{ConvertOptions, PreserveOptions, ResizeOptions, StoreOptions};
+use crate::options::{
+    validate_background, ConvertOptions, ImageFormat, PreserveOptions, ResizeMethod, ResizeOptions,
+    StoreOptions, TransformOptions,
+};
 use crate::result::TinifyResult;
-use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::future::BoxFuture;
+#[cfg(target_arch = "wasm32")]
+use futures_util::future::LocalBoxFuture as BoxFuture;
+use serde_json::{Map, Value};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "fs")]
+use tracing::warn;
 use tracing::{info, instrument};
 
+/// Files and `srcset` attribute produced by [`Source::write_responsive_set`]
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct ResponsiveImageSet {
+    /// Paths of the files that were written, in the order of the requested sizes
+    pub files: Vec<PathBuf>,
+    /// `srcset` attribute value, e.g. `"photo-320x240.png 320w, photo-640x480.png 640w"`
+    pub srcset: String,
+    /// Sizes that failed, paired with their error
+    pub errors: Vec<(ResizeOptions, crate::error::TinifyError)>,
+}
+
 /// Represents an image source uploaded to Tinify
 ///
 /// `Source` objects represent images that have been uploaded to Tinify servers,
 /// allowing various operations such as resizing, format conversion, metadata preservation, etc.
+///
+/// # Pipelines
+///
+/// The `with_*` methods return a new `Source` that records an operation instead of
+/// running it. Recorded operations are sent together in a single request when the
+/// pipeline is materialized by [`Source::result`], [`Source::to_buffer`],
+/// `Source::to_file`, [`Source::store`] or one of the terminal operations, so no
+/// intermediate images are downloaded or re-uploaded. Quota-wise, resizing and
+/// converting each count as one compression whether chained or run separately, while
+/// preserving metadata and storing do not count.
+///
+/// # Expiry
+///
+/// The upload behind a `Source` is kept server-side for a limited, unspecified
+/// time, and nothing a client does extends it. Operations on an expired source
+/// fail with a 404 or 410 client error. Run long pipelines soon after uploading, and
+/// use [`Source::touch`] or [`Source::is_valid`] to detect expiry before starting
+/// work on a source that has been held for a while.
 #[derive(Debug, Clone)]
 pub struct Source {
     location: String,
     client: Arc<Client>,
+    commands: Map<String, Value>,
+    /// Dimensions reported by operations on this upload, keyed by the operations
+    /// that produced them and shared by its copies
+    dimensions: Arc<Mutex<HashMap<String, (u32, u32)>>>,
+    /// Dimensions of the uploaded image, if the upload response reported them
+    original_dimensions: Option<(u32, u32)>,
 }
 
 impl Source {
@@ -23,7 +73,339 @@ impl Source {
     /// * `location` - Image location URL on Tinify servers
     /// * `client` - Arc reference to the HTTP client
     pub fn new(location: String, client: Arc<Client>) -> Self {
-        Self { location, client }
+        Self {
+            location,
+            client,
+            commands: Map::new(),
+            dimensions: Arc::default(),
+            original_dimensions: None,
+        }
+    }
+
+    pub(crate) fn with_original_dimensions(mut self, dimensions: Option<(u32, u32)>) -> Self {
+        self.original_dimensions = dimensions;
+        self
+    }
+
+    /// Recreate a Source from a location saved earlier with [`Source::location`]
+    ///
+    /// Lets a service upload an image in one request, store the location (e.g. in a
+    /// database) and process it later without uploading again. Pending pipeline
+    /// operations are not part of the location and are not restored.
+    ///
+    /// Tinify only keeps uploaded images for a limited time, so saved locations expire;
+    /// operations on an expired location fail with a client error and the image has to
+    /// be uploaded again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::UrlParseError`](crate::TinifyError::UrlParseError) if
+    /// `location` is not a valid URL.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Source, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let saved = client.source_from_file("input.png").await?.location().to_string();
+    ///
+    /// // Later, possibly in another process
+    /// let source = Source::from_location(&saved, &client)?;
+    /// source.to_file("output.png").await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub fn from_location(location: &str, tinify: &crate::Tinify) -> Result<Source> {
+        url::Url::parse(location)?;
+        Ok(Source::new(
+            location.to_string(),
+            Arc::clone(&tinify.client),
+        ))
+    }
+
+    /// Check whether the upload behind this source is still available
+    ///
+    /// Tinify keeps uploaded images for a limited time only and does not guarantee a
+    /// fixed window, so a source held for hours or restored with
+    /// [`Source::from_location`] may have expired. This sends a `HEAD` request to the
+    /// location, which downloads nothing and does not count as a compression, and
+    /// returns `Ok(false)` if the server answers 404 or 410. Long-lived services can
+    /// use it to upload the image again before running operations.
+    ///
+    /// # Errors
+    ///
+    /// Any other failure, e.g. a connection error or an invalid API key, is returned
+    /// as is, since it says nothing about whether the location has expired.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Source, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// # let saved = String::new();
+    /// let mut source = Source::from_location(&saved, &client)?;
+    /// if !source.is_valid().await? {
+    ///     source = client.source_from_file("input.png").await?;
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self), fields(location = %self.location))]
+    pub async fn is_valid(&self) -> Result<bool> {
+        match self.client.head(&self.location).await {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.status_code(), Some(404 | 410)) => {
+                info!("Source location has expired: {}", self.location);
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Check that the upload is still available before a long pipeline
+    ///
+    /// The API has no way to keep an upload alive, so this does not extend its
+    /// lifetime (see [expiry](Source#expiry)). It makes the same lightweight `HEAD`
+    /// request as [`Source::is_valid`] and returns its result, so expiry shows up
+    /// early instead of partway through a pipeline.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ResizeOptions, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let mut source = client.source_from_file("input.png").await?;
+    /// // ... time passes ...
+    /// if !source.touch().await? {
+    ///     source = client.source_from_file("input.png").await?;
+    /// }
+    /// source.resize(ResizeOptions::scale_width(800)).await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub async fn touch(&self) -> Result<bool> {
+        self.is_valid().await
+    }
+
+    fn validate_resize(&self, options: &ResizeOptions) -> Result<()> {
+        let (width, height) = (options.width, options.height);
+        match options.method {
+            ResizeMethod::Scale if width.is_some() && height.is_some() => {
+                Err(crate::error::TinifyError::InvalidOptions {
+                    message: "Scale takes either a width or a height, not both; use \
+                              ResizeOptions::fit to scale within a box keeping the aspect ratio"
+                        .to_string(),
+                })
+            }
+            ResizeMethod::Fit | ResizeMethod::Cover | ResizeMethod::Thumb
+                if width.is_none() || height.is_none() =>
+            {
+                let missing = match (width, height) {
+                    (None, None) => "width and height are missing",
+                    (None, _) => "width is missing",
+                    _ => "height is missing",
+                };
+                Err(crate::error::TinifyError::InvalidOptions {
+                    message: format!(
+                        "{:?} requires both width and height; {}",
+                        options.method, missing
+                    ),
+                })
+            }
+            _ => crate::Tinify::validate_dimensions(width, height, self.client.max_dimension()),
+        }
+    }
+
+    fn validate_convert(&self, options: &ConvertOptions) -> Result<()> {
+        if let Some(background) = &options.background {
+            validate_background(background)?;
+
+            if !matches!(options.format, ImageFormat::Jpeg) {
+                let message = format!(
+                    "Background color {} has no effect when converting to {:?}, which supports transparency",
+                    background, options.format
+                );
+                self.client.soft_violation(message)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_preserve(&self, options: &PreserveOptions) -> Result<()> {
+        if options.preserve.is_empty() {
+            self.client
+                .soft_violation("Preserving an empty list of metadata has no effect".to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Return a copy of this source with `key` added to the pending operations
+    fn with_command(&self, key: &str, value: Value) -> Self {
+        let mut source = self.clone();
+        source.commands.insert(key.to_string(), value);
+        source
+    }
+
+    /// Send the pending operations plus `key`, if any, as a single request
+    async fn post_commands(&self, command: Option<(&str, Value)>) -> Result<TinifyResult> {
+        let mut commands = self.commands.clone();
+        if let Some((key, value)) = command {
+            commands.insert(key.to_string(), value);
+        }
+
+        let body = RequestBody::json(&commands)?;
+        let response = self
+            .client
+            .post(&self.location, Some(body))
+            .await
+            .map_err(|err| unavailable_format(err, commands.get("convert")))?;
+        let counted = commands.contains_key("resize") || commands.contains_key("convert");
+
+        let mut origin = self.clone();
+        commands.remove("store");
+        let key = dimensions_key(&commands);
+        origin.commands = commands;
+        let result = TinifyResult::new(response)
+            .counted(counted)
+            .with_source(origin);
+
+        if let (Some(width), Some(height)) = (result.image_width(), result.image_height()) {
+            self.dimensions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, (width, height));
+        }
+        Ok(result)
+    }
+
+    /// Add a resize step to the pipeline
+    ///
+    /// The options are validated immediately, but no request is made until the
+    /// pipeline is materialized. See the [pipeline docs](Source#pipelines).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Tinify, ResizeOptions, ConvertOptions, ImageFormat};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let webp_thumbnail = source
+    ///     .with_resize(&ResizeOptions::cover(150, 150))?
+    ///     .with_convert(&ConvertOptions::new(ImageFormat::WebP))?
+    ///     .to_buffer()
+    ///     .await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    pub fn with_resize(&self, options: &ResizeOptions) -> Result<Source> {
+        self.validate_resize(options)?;
+        Ok(self.with_command("resize", serde_json::to_value(options)?))
+    }
+
+    /// Add a format conversion step to the pipeline
+    ///
+    /// See [`Source::with_resize`] and the [pipeline docs](Source#pipelines).
+    pub fn with_convert(&self, options: &ConvertOptions) -> Result<Source> {
+        self.validate_convert(options)?;
+        Ok(self.with_command("convert", serde_json::to_value(options)?))
+    }
+
+    /// Add a metadata preservation step to the pipeline
+    ///
+    /// See [`Source::with_resize`] and the [pipeline docs](Source#pipelines).
+    pub fn with_preserve(&self, options: &PreserveOptions) -> Result<Source> {
+        self.validate_preserve(options)?;
+        Ok(self.with_command("preserve", serde_json::to_value(&options.preserve)?))
+    }
+
+    /// Materialize the pipeline into a result
+    ///
+    /// Sends all pending operations in one request. Without pending operations this
+    /// fetches the compressed image as-is.
+    ///
+    /// Only the response headers are read, so metadata such as
+    /// [`TinifyResult::image_width`] and [`TinifyResult::content_length`] can be inspected
+    /// before deciding whether to download the body.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ResizeOptions, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let mut result = source.with_resize(&ResizeOptions::scale_width(800))?.result().await?;
+    /// if result.content_length().is_some_and(|len| len < 500_000) {
+    ///     result.to_file("output.png").await?;
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self), fields(location = %self.location))]
+    pub async fn result(&self) -> Result<TinifyResult> {
+        if self.commands.is_empty() {
+            let response = self.client.get(&self.location).await?;
+            return Ok(TinifyResult::new(response).with_source(self.clone()));
+        }
+
+        info!(
+            "Applying {} pending operations at location: {}",
+            self.commands.len(),
+            self.location
+        );
+        self.post_commands(None).await
+    }
+
+    /// Download the compressed image in the best of several formats the server supports
+    ///
+    /// Sends `formats` as the `Accept` header, e.g. `Accept: image/webp, image/avif`,
+    /// so the server can answer with the representation it prefers instead of the
+    /// input format. Check [`TinifyResult::format`] or [`TinifyResult::content_type`]
+    /// for the format that was chosen. Pending pipeline operations are not applied;
+    /// use [`Source::convert`] to require a specific format.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ImageFormat, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let mut result = source
+    ///     .result_accepting(&[ImageFormat::WebP, ImageFormat::Avif])
+    ///     .await?;
+    /// println!("Server chose {:?}", result.content_type());
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self), fields(location = %self.location))]
+    pub async fn result_accepting(&self, formats: &[ImageFormat]) -> Result<TinifyResult> {
+        if formats.is_empty() {
+            return Err(crate::error::TinifyError::InvalidOptions {
+                message: "result_accepting requires at least one format".to_string(),
+            });
+        }
+
+        let accept = formats
+            .iter()
+            .map(ImageFormat::mime_type)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let response = self.client.get_accepting(&self.location, &accept).await?;
+        Ok(TinifyResult::new(response).with_source(self.clone()))
     }
 
     /// Resize the image
@@ -32,7 +414,8 @@ impl Source {
     ///
     /// # Arguments
     ///
-    /// * `options` - Resize options including resize method, width, height, etc.
+    /// * `options` - Resize options including resize method, width, height, etc. Pass
+    ///   them by reference to reuse them across sources without cloning.
     ///
     /// # Examples
     ///
@@ -49,20 +432,208 @@ impl Source {
     ///     height: Some(200),
     /// };
     ///
-    /// let result = source.resize(resize_options).await?;
+    /// let result = source.resize(&resize_options).await?;
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
-    #[instrument(skip(self), fields(location = %self.location))]
-    pub async fn resize(&self, options: ResizeOptions) -> Result<TinifyResult> {
+    #[instrument(skip(self, options), fields(location = %self.location, options = ?options.borrow()))]
+    pub async fn resize(&self, options: impl Borrow<ResizeOptions>) -> Result<TinifyResult> {
+        let options = options.borrow();
         info!("Resizing image at location: {}", self.location);
 
         // Validate resize options
-        crate::Tinify::validate_dimensions(options.width, options.height)?;
+        self.validate_resize(options)?;
 
-        let body = serde_json::to_vec(&serde_json::json!({ "resize": options }))?;
-        let response = self.client.post(&self.location, Some(body)).await?;
-        Ok(TinifyResult::new(response))
+        self.post_commands(Some(("resize", serde_json::to_value(options)?)))
+            .await
+    }
+
+    /// Resize the image to several sizes
+    ///
+    /// Runs one resize per entry in `sizes`, reusing this source's location so the
+    /// image is only uploaded once. Requests go through the client's rate limiter,
+    /// and a failure for one size does not abort the others. Each outcome is paired
+    /// with the options that produced it, in the order of `sizes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sizes` - Resize options for each requested size
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Tinify, ResizeOptions};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let sizes: Vec<ResizeOptions> = [320, 640, 1280]
+    ///     .into_iter()
+    ///     .map(ResizeOptions::scale_width)
+    ///     .collect();
+    ///
+    /// for (options, result) in source.resize_many(&sizes).await {
+    ///     match result {
+    ///         Ok(r) => println!("Resized to {:?}x{:?}", r.image_width(), r.image_height()),
+    ///         Err(e) => println!("Resize to {:?} failed: {}", options.width, e),
+    ///     }
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, sizes), fields(location = %self.location, count = sizes.len()))]
+    pub async fn resize_many(
+        &self,
+        sizes: &[ResizeOptions],
+    ) -> Vec<(ResizeOptions, Result<TinifyResult>)> {
+        info!(
+            "Resizing image at location {} to {} sizes",
+            self.location,
+            sizes.len()
+        );
+
+        let mut results = Vec::with_capacity(sizes.len());
+        for options in sizes {
+            results.push((options.clone(), self.resize(options).await));
+        }
+        results
+    }
+
+    /// Convert the image to several formats
+    ///
+    /// The format counterpart of [`Source::resize_many`]: runs one conversion per entry
+    /// in `formats` on this source's location, e.g. to build a PNG, WebP and AVIF set
+    /// from a single upload. A failure for one format does not abort the others, and
+    /// each conversion counts as one compression. Each outcome is paired with its
+    /// options, so a failed format can be told apart from the others.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ConvertOptions, ImageFormat, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let formats = [ImageFormat::WebP, ImageFormat::Avif].map(ConvertOptions::new);
+    /// for (options, result) in source.convert_variants(&formats).await {
+    ///     match result {
+    ///         Ok(r) => println!("Converted to {:?}", r.content_type()),
+    ///         Err(e) => println!("Conversion to {:?} failed: {}", options.format, e),
+    ///     }
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, formats), fields(location = %self.location, count = formats.len()))]
+    pub async fn convert_variants(
+        &self,
+        formats: &[ConvertOptions],
+    ) -> Vec<(ConvertOptions, Result<TinifyResult>)> {
+        info!(
+            "Converting image at location {} to {} formats",
+            self.location,
+            formats.len()
+        );
+
+        let mut results = Vec::with_capacity(formats.len());
+        for options in formats {
+            results.push((options.clone(), self.convert(options).await));
+        }
+        results
+    }
+
+    /// Write a responsive image set to disk
+    ///
+    /// Resizes the image to each of `sizes` and saves the results as
+    /// `{stem}-{width}x{height}.{extension}` inside `dir`. Returns the written files
+    /// together with a `srcset` attribute value and any per-size errors. A size whose
+    /// width or height is neither reported by the API nor fixed by its options is
+    /// reported as an error rather than written under a made-up name.
+    ///
+    /// # Arguments
+    ///
+    /// * `sizes` - Resize options for each requested size
+    /// * `dir` - Directory the files are written to
+    /// * `stem` - Base file name, e.g. `"photo"`
+    /// * `extension` - File extension without the dot, e.g. `"png"`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{Tinify, ResizeOptions};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("photo.png").await?;
+    ///
+    /// let sizes: Vec<ResizeOptions> = [320, 640]
+    ///     .into_iter()
+    ///     .map(ResizeOptions::scale_width)
+    ///     .collect();
+    ///
+    /// let set = source.write_responsive_set(&sizes, "public/img", "photo", "png").await?;
+    /// println!("<img srcset=\"{}\">", set.srcset);
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[cfg(feature = "fs")]
+    #[instrument(skip(self, sizes, dir), fields(location = %self.location))]
+    pub async fn write_responsive_set<P: AsRef<Path>>(
+        &self,
+        sizes: &[ResizeOptions],
+        dir: P,
+        stem: &str,
+        extension: &str,
+    ) -> Result<ResponsiveImageSet> {
+        let dir = dir.as_ref();
+        let mut set = ResponsiveImageSet {
+            files: Vec::new(),
+            srcset: String::new(),
+            errors: Vec::new(),
+        };
+        let mut srcset_entries = Vec::new();
+
+        for (options, result) in self.resize_many(sizes).await {
+            let mut result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Skipping size {:?}: {}", options, e);
+                    set.errors.push((options, e));
+                    continue;
+                }
+            };
+
+            let (Some(width), Some(height)) = (
+                result.image_width().or(options.width),
+                result.image_height().or(options.height),
+            ) else {
+                warn!("Skipping size {:?}: dimensions unknown", options);
+                set.errors.push((
+                    options,
+                    crate::error::TinifyError::UnknownError {
+                        message: "Response did not report the image dimensions".to_string(),
+                        status: None,
+                    },
+                ));
+                continue;
+            };
+            let file_name = format!("{}-{}x{}.{}", stem, width, height, extension);
+            let path = dir.join(&file_name);
+
+            if let Err(e) = result.to_file(&path).await {
+                set.errors.push((options.clone(), e));
+                continue;
+            }
+
+            srcset_entries.push(format!("{} {}w", file_name, width));
+            set.files.push(path);
+        }
+
+        set.srcset = srcset_entries.join(", ");
+        Ok(set)
     }
 
     /// Convert image format
@@ -91,13 +662,15 @@ impl Source {
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
-    #[instrument(skip(self), fields(location = %self.location))]
-    pub async fn convert(&self, options: ConvertOptions) -> Result<TinifyResult> {
+    #[instrument(skip(self, options), fields(location = %self.location, options = ?options.borrow()))]
+    pub async fn convert(&self, options: impl Borrow<ConvertOptions>) -> Result<TinifyResult> {
+        let options = options.borrow();
         info!("Converting image format at location: {}", self.location);
 
-        let body = serde_json::to_vec(&serde_json::json!({ "convert": options }))?;
-        let response = self.client.post(&self.location, Some(body)).await?;
-        Ok(TinifyResult::new(response))
+        self.validate_convert(options)?;
+
+        self.post_commands(Some(("convert", serde_json::to_value(options)?)))
+            .await
     }
 
     /// Preserve image metadata
@@ -128,16 +701,82 @@ impl Source {
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
-    #[instrument(skip(self), fields(location = %self.location))]
-    pub async fn preserve(&self, options: PreserveOptions) -> Result<TinifyResult> {
+    #[instrument(skip(self, options), fields(location = %self.location, options = ?options.borrow()))]
+    pub async fn preserve(&self, options: impl Borrow<PreserveOptions>) -> Result<TinifyResult> {
+        let options = options.borrow();
         info!(
             "Preserving metadata for image at location: {}",
             self.location
         );
 
-        let body = serde_json::to_vec(&options)?;
-        let response = self.client.post(&self.location, Some(body)).await?;
-        Ok(TinifyResult::new(response))
+        self.validate_preserve(options)?;
+        self.post_commands(Some(("preserve", serde_json::to_value(&options.preserve)?)))
+            .await
+    }
+
+    /// Apply several operations in a single request
+    ///
+    /// Combines resizing, conversion and metadata preservation so they count as one
+    /// compression instead of one per re-uploaded intermediate image. See
+    /// [`TransformOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TinifyError::InvalidOptions`](crate::TinifyError::InvalidOptions) if no
+    /// operation is set, or the usual validation errors for the resize and convert options.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{ConvertOptions, ImageFormat, PreserveMetadata, Tinify, TransformOptions};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.jpg").await?;
+    ///
+    /// let options = TransformOptions::new()
+    ///     .convert(ConvertOptions::new(ImageFormat::WebP))
+    ///     .preserve(vec![PreserveMetadata::Copyright]);
+    /// let result = source.transform(options).await?;
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self), fields(location = %self.location))]
+    pub async fn transform(&self, options: TransformOptions) -> Result<TinifyResult> {
+        info!("Transforming image at location: {}", self.location);
+
+        if options.resize.is_none() && options.convert.is_none() && options.preserve.is_none() {
+            return Err(crate::error::TinifyError::InvalidOptions {
+                message: "transform requires at least one operation".to_string(),
+            });
+        }
+
+        // Validate every bundled operation before serializing any of them, with the
+        // same rules as the standalone resize and convert calls
+        if let Some(resize) = &options.resize {
+            self.validate_resize(resize)?;
+        }
+        if let Some(convert) = &options.convert {
+            self.validate_convert(convert)?;
+        }
+        if let Some(preserve) = &options.preserve {
+            self.validate_preserve(&PreserveOptions {
+                preserve: preserve.clone(),
+            })?;
+        }
+
+        let mut source = self.clone();
+        if let Some(resize) = &options.resize {
+            source = source.with_command("resize", serde_json::to_value(resize)?);
+        }
+        if let Some(convert) = &options.convert {
+            source = source.with_command("convert", serde_json::to_value(convert)?);
+        }
+        if let Some(preserve) = &options.preserve {
+            source = source.with_command("preserve", serde_json::to_value(preserve)?);
+        }
+
+        source.post_commands(None).await
     }
 
     /// Store image to cloud storage service
@@ -152,7 +791,7 @@ impl Source {
     ///
     /// ```no_run
     /// # tokio_test::block_on(async {
-    /// use tinify::{Tinify, StoreOptions, S3Options};
+    /// use tinify::{Tinify, StoreOptions, S3Acl, S3Options};
     ///
     /// let client = Tinify::new("your-api-key".to_string())?;
     /// let source = client.source_from_file("input.jpg").await?;
@@ -164,24 +803,73 @@ impl Source {
     ///     region: "us-east-1".to_string(),
     ///     path: "bucket/path/image.jpg".to_string(),
     ///     headers: None,
-    ///     acl: Some("public-read".to_string()),
+    ///     acl: Some(S3Acl::PublicRead),
+    ///     endpoint: None,
     /// };
     ///
     /// let result = source.store(StoreOptions::S3(s3_options)).await?;
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
-    #[instrument(skip(self), fields(location = %self.location))]
-    pub async fn store(&self, options: StoreOptions) -> Result<TinifyResult> {
+    #[instrument(skip(self, options), fields(location = %self.location, options = ?options.borrow()))]
+    pub async fn store(&self, options: impl Borrow<StoreOptions>) -> Result<TinifyResult> {
+        let options = options.borrow();
         info!(
             "Storing image to cloud storage from location: {}",
             self.location
         );
 
-        let store_request = crate::options::StoreRequest { store: options };
-        let body = serde_json::to_vec(&store_request)?;
-        let response = self.client.post(&self.location, Some(body)).await?;
-        Ok(TinifyResult::new(response))
+        options.validate()?;
+        self.post_commands(Some(("store", serde_json::to_value(options)?)))
+            .await
+    }
+
+    /// Store the image to several destinations concurrently
+    ///
+    /// Sends one store request per destination at the same time, subject to the
+    /// client's rate limiter, and returns the results in the order of `destinations`.
+    /// A failing destination doesn't affect the others. Storing doesn't count as a
+    /// compression, so this costs no extra quota.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use tinify::{S3Options, StoreOptions, Tinify};
+    ///
+    /// let client = Tinify::new("your-api-key".to_string())?;
+    /// let source = client.source_from_file("input.png").await?;
+    ///
+    /// let destinations = ["us-east-1", "eu-west-1"]
+    ///     .iter()
+    ///     .map(|region| {
+    ///         StoreOptions::S3(S3Options::new(
+    ///             "key".to_string(),
+    ///             "secret".to_string(),
+    ///             region.to_string(),
+    ///             format!("images-{}/photo.png", region),
+    ///         ))
+    ///     })
+    ///     .collect();
+    ///
+    /// for result in source.store_many(destinations).await {
+    ///     if let Err(err) = result {
+    ///         eprintln!("Store failed: {}", err);
+    ///     }
+    /// }
+    /// # Ok::<(), tinify::TinifyError>(())
+    /// # });
+    /// ```
+    #[instrument(skip(self, destinations), fields(location = %self.location, destinations = destinations.len()))]
+    pub async fn store_many(&self, destinations: Vec<StoreOptions>) -> Vec<Result<TinifyResult>> {
+        info!(
+            "Storing image from location {} to {} destinations",
+            self.location,
+            destinations.len()
+        );
+
+        futures_util::future::join_all(destinations.into_iter().map(|options| self.store(options)))
+            .await
     }
 
     /// Get image data to memory buffer
@@ -206,14 +894,18 @@ impl Source {
     pub async fn to_buffer(&self) -> Result<Vec<u8>> {
         info!("Downloading image data from location: {}", self.location);
 
-        let response = self.client.get(&self.location).await?;
-        let mut result = TinifyResult::new(response);
-        result.to_buffer().await
+        if self.commands.is_empty() {
+            self.client.download(&self.location).await
+        } else {
+            self.post_commands(None).await?.to_buffer().await
+        }
     }
 
     /// Save image to local file
     ///
     /// Download processed image and save it to the specified local file path.
+    /// To read the result's metadata before (or instead of) saving, use
+    /// [`Source::result`] followed by [`TinifyResult::to_file`].
     ///
     /// # Arguments
     ///
@@ -233,6 +925,7 @@ impl Source {
     /// # Ok::<(), tinify::TinifyError>(())
     /// # });
     /// ```
+    #[cfg(feature = "fs")]
     #[instrument(skip(self), fields(location = %self.location, path = %path.as_ref().display()))]
     pub async fn to_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
         let path_display = path.as_ref().display().to_string();
@@ -241,13 +934,1036 @@ impl Source {
             self.location, path_display
         );
 
-        let response = self.client.get(&self.location).await?;
-        let mut result = TinifyResult::new(response);
-        result.to_file(path).await
+        let bytes = self.to_buffer().await?;
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
     }
 
     /// Get the location URL for this source
+    ///
+    /// The location can be stored and turned back into a `Source` with
+    /// [`Source::from_location`] until it expires server-side.
     pub fn location(&self) -> &str {
         &self.location
     }
+
+    /// Dimensions of the image this source's pending operations produce, if known
+    ///
+    /// Operations that produce an image (resizing, converting, compressing through a
+    /// pipeline) report its width and height, but storing may not. Reported
+    /// dimensions are remembered per set of operations, so they can be recorded after
+    /// a resize-then-store of the same pipeline without an extra request. Copies of
+    /// this source made with the `with_*` methods share what has been learned.
+    /// Returns `None` until an operation with the same pending operations has
+    /// reported them.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&dimensions_key(&self.commands))
+            .copied()
+    }
+
+    /// Width and height of the image as uploaded, before any operation
+    ///
+    /// Read from `output.width` and `output.height` in the upload response body, or
+    /// its `Image-Width` and `Image-Height` headers, so learning the input size
+    /// doesn't need a resize. Returns `None` if the upload
+    /// response didn't include them, or for sources created with [`Source::new`] or
+    /// [`Source::from_location`].
+    pub fn original_dimensions(&self) -> Option<(u32, u32)> {
+        self.original_dimensions
+    }
+}
+
+/// Key under which the dimensions produced by `commands` are remembered
+fn dimensions_key(commands: &Map<String, Value>) -> String {
+    Value::Object(commands.clone()).to_string()
+}
+
+/// Report a refused conversion as [`TinifyError::FormatNotAvailable`]
+///
+/// The API answers with a client or account error when the target format isn't
+/// offered for the account or region. It counts as such when the status is 415, or
+/// the message both names the format and says it is not supported or available, so
+/// unrelated errors of a combined request pass through unchanged even when they
+/// mention the format.
+///
+/// [`TinifyError::FormatNotAvailable`]: crate::TinifyError::FormatNotAvailable
+fn unavailable_format(
+    err: crate::error::TinifyError,
+    convert: Option<&Value>,
+) -> crate::error::TinifyError {
+    use crate::error::TinifyError;
+
+    let Some(format) = convert
+        .and_then(|value| serde_json::from_value::<ConvertOptions>(value.clone()).ok())
+        .map(|options| options.format)
+    else {
+        return err;
+    };
+    match &err {
+        TinifyError::ClientError {
+            message, status, ..
+        }
+        | TinifyError::AccountError {
+            message, status, ..
+        } => {
+            let message = message.to_lowercase();
+            let subtype = format.mime_type().trim_start_matches("image/");
+            let names_format = message.contains(format.extension()) || message.contains(subtype);
+            let refused = [
+                "not supported",
+                "not available",
+                "unsupported",
+                "unavailable",
+            ]
+            .iter()
+            .any(|phrase| message.contains(phrase));
+            if *status == Some(415) || (names_format && refused) {
+                TinifyError::FormatNotAvailable { format }
+            } else {
+                err
+            }
+        }
+        _ => err,
+    }
+}
+
+/// Object-safe subset of the [`Source`] operations
+///
+/// Lets code hold uploaded images as `Box<dyn Processable>` or `Arc<dyn Processable>`
+/// and process them without depending on the concrete type. The methods behave like
+/// their inherent counterparts on [`Source`], which remain the simplest way to use a
+/// single source directly.
+///
+/// # Examples
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use tinify::{Processable, ResizeOptions, Tinify};
+///
+/// let client = Tinify::new("your-api-key".to_string())?;
+/// let uploads: Vec<Box<dyn Processable>> = vec![
+///     Box::new(client.source_from_file("first.png").await?),
+///     Box::new(client.source_from_file("second.png").await?),
+/// ];
+///
+/// for upload in &uploads {
+///     let thumbnail = upload.resize(&ResizeOptions::cover(150, 150)).await?.to_buffer().await?;
+///     println!("Thumbnail is {} bytes", thumbnail.len());
+/// }
+/// # Ok::<(), tinify::TinifyError>(())
+/// # });
+/// ```
+pub trait Processable: Send + Sync {
+    /// See [`Source::resize`]
+    fn resize<'a>(&'a self, options: &'a ResizeOptions) -> BoxFuture<'a, Result<TinifyResult>>;
+
+    /// See [`Source::convert`]
+    fn convert<'a>(&'a self, options: &'a ConvertOptions) -> BoxFuture<'a, Result<TinifyResult>>;
+
+    /// See [`Source::to_buffer`]
+    fn to_buffer(&self) -> BoxFuture<'_, Result<Vec<u8>>>;
+}
+
+impl Processable for Source {
+    fn resize<'a>(&'a self, options: &'a ResizeOptions) -> BoxFuture<'a, Result<TinifyResult>> {
+        Box::pin(Source::resize(self, options))
+    }
+
+    fn convert<'a>(&'a self, options: &'a ConvertOptions) -> BoxFuture<'a, Result<TinifyResult>> {
+        Box::pin(Source::convert(self, options))
+    }
+
+    fn to_buffer(&self) -> BoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(Source::to_buffer(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::PreserveMetadata;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    fn mock_source(server: &mockito::Server) -> Source {
+        let client = Arc::new(Client::new("test-key".to_string()).unwrap());
+        Source::new(format!("{}/output/abc", server.url()), client)
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_result_metadata_before_saving() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .with_header("Image-Width", "320")
+            .with_header("Image-Height", "240")
+            .with_body("resized")
+            .create_async()
+            .await;
+
+        let source = mock_source(&server)
+            .with_resize(&ResizeOptions::fit(320, 240))
+            .unwrap();
+        let mut result = source.result().await.unwrap();
+
+        assert_eq!(result.image_width(), Some(320));
+        assert_eq!(result.image_height(), Some(240));
+        assert_eq!(result.content_length(), Some(7));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("resized.png");
+        result.to_file(&path).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"resized");
+        assert_eq!(result.image_width(), Some(320));
+    }
+
+    #[tokio::test]
+    async fn test_transform_sends_preserve_with_convert() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::Json(json!({
+                "convert": { "type": "image/webp" },
+                "preserve": ["copyright"],
+            })))
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let options = TransformOptions::new()
+            .convert(ConvertOptions::new(ImageFormat::WebP))
+            .preserve(vec![PreserveMetadata::Copyright]);
+        source.transform(options).await.unwrap();
+        mock.assert_async().await;
+
+        assert!(matches!(
+            source.transform(TransformOptions::new()).await,
+            Err(crate::error::TinifyError::InvalidOptions { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_transform_validates_bundled_options() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let options = TransformOptions::new()
+            .resize(ResizeOptions {
+                method: ResizeMethod::Scale,
+                width: Some(0),
+                height: None,
+            })
+            .convert(ConvertOptions::new(ImageFormat::WebP));
+        assert!(matches!(
+            source.transform(options).await,
+            Err(crate::error::TinifyError::InvalidDimensions { .. })
+        ));
+
+        let mut convert = ConvertOptions::new(ImageFormat::Jpeg);
+        convert.background = Some("not-a-color".to_string());
+        let options = TransformOptions::new()
+            .resize(ResizeOptions {
+                method: ResizeMethod::Scale,
+                width: Some(100),
+                height: None,
+            })
+            .convert(convert);
+        assert!(matches!(
+            source.transform(options).await,
+            Err(crate::error::TinifyError::InvalidBackground { .. })
+        ));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_source_from_saved_location() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::Json(
+                json!({ "resize": { "method": "scale", "width": 50 } }),
+            ))
+            .with_status(200)
+            .with_body("resized")
+            .create_async()
+            .await;
+
+        let client = crate::Tinify::new("test-key".to_string()).unwrap();
+        let saved = mock_source(&server).location().to_string();
+
+        let source = Source::from_location(&saved, &client).unwrap();
+        assert_eq!(source.location(), saved);
+        let mut result = source.resize(ResizeOptions::scale_width(50)).await.unwrap();
+        assert_eq!(result.to_buffer().await.unwrap(), b"resized");
+        mock.assert_async().await;
+
+        assert!(Source::from_location("not a url", &client).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_avif_maps_to_format_not_available() {
+        let mut server = mockito::Server::new_async().await;
+        let _avif = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(
+                json!({ "convert": { "type": "image/avif" } }),
+            ))
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Conversion to AVIF is not supported"}"#)
+            .create_async()
+            .await;
+        let _resize = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(
+                json!({ "convert": { "type": "image/webp" } }),
+            ))
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Invalid resize parameters"}"#)
+            .create_async()
+            .await;
+        let _mentions_format = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(
+                json!({ "convert": { "type": "image/png" } }),
+            ))
+            .with_status(400)
+            .with_body(
+                r#"{"error":"BadRequest","message":"Resize width exceeds the PNG output limit"}"#,
+            )
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let err = source
+            .convert(ConvertOptions::new(ImageFormat::Avif))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::TinifyError::FormatNotAvailable {
+                format: ImageFormat::Avif
+            }
+        ));
+
+        // Errors that don't concern the target format are left alone, even when
+        // they mention it
+        for format in [ImageFormat::WebP, ImageFormat::Png] {
+            let err = source
+                .convert(ConvertOptions::new(format))
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                crate::error::TinifyError::ClientError {
+                    status: Some(400),
+                    ..
+                }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resize_many_pairs_sizes_with_results() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for (width, status) in [(320, 200), (640, 400), (1280, 200)] {
+            let mock = server
+                .mock("POST", "/output/abc")
+                .match_body(Matcher::Json(
+                    json!({ "resize": { "method": "scale", "width": width } }),
+                ))
+                .with_status(status)
+                .with_body(r#"{"error":"BadRequest","message":"Resize failed"}"#)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+
+        let sizes = [320, 640, 1280].map(ResizeOptions::scale_width);
+        let results = mock_source(&server).resize_many(&sizes).await;
+
+        let outcomes: Vec<_> = results
+            .iter()
+            .map(|(options, result)| (options.width, result.is_ok()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [(Some(320), true), (Some(640), false), (Some(1280), true)]
+        );
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_convert_variants() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for (mime, body, status) in [
+            ("image/png", "png", 200),
+            ("image/webp", "webp", 200),
+            ("image/avif", "avif", 200),
+            ("image/jpeg", "", 500),
+        ] {
+            let mock = server
+                .mock("POST", "/output/abc")
+                .match_body(Matcher::Json(json!({ "convert": { "type": mime } })))
+                .with_status(status)
+                .with_header("Content-Type", mime)
+                .with_body(body)
+                .create_async()
+                .await;
+            mocks.push(mock);
+        }
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .max_retry_attempts(1)
+            .build()
+            .unwrap();
+        let source = Source::new(format!("{}/output/abc", server.url()), Arc::new(client));
+        let formats = [
+            ImageFormat::Png,
+            ImageFormat::WebP,
+            ImageFormat::Avif,
+            ImageFormat::Jpeg,
+        ]
+        .map(ConvertOptions::new);
+
+        let mut results = source.convert_variants(&formats).await.into_iter();
+        let (options, png) = results.next().unwrap();
+        assert_eq!(options.format, ImageFormat::Png);
+        assert_eq!(png.unwrap().to_buffer().await.unwrap(), b"png");
+        let (options, webp) = results.next().unwrap();
+        assert_eq!(options.format, ImageFormat::WebP);
+        let mut webp = webp.unwrap();
+        assert_eq!(webp.content_type().as_deref(), Some("image/webp"));
+        assert_eq!(webp.to_buffer().await.unwrap(), b"webp");
+        let (options, avif) = results.next().unwrap();
+        assert_eq!(options.format, ImageFormat::Avif);
+        assert_eq!(avif.unwrap().to_buffer().await.unwrap(), b"avif");
+        // A failed format is reported with its own options
+        let (options, jpeg) = results.next().unwrap();
+        assert_eq!(options.format, ImageFormat::Jpeg);
+        assert!(matches!(
+            jpeg,
+            Err(crate::error::TinifyError::ServerError { .. })
+        ));
+        assert!(results.next().is_none());
+
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_result_accepting_sends_accept_header() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/output/abc")
+            .match_header("Accept", "image/webp, image/avif")
+            .with_status(200)
+            .with_header("Content-Type", "image/avif")
+            .with_body("avif")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let mut result = source
+            .result_accepting(&[ImageFormat::WebP, ImageFormat::Avif])
+            .await
+            .unwrap();
+        assert_eq!(result.content_type().as_deref(), Some("image/avif"));
+        assert_eq!(result.format(), Some(ImageFormat::Avif));
+        assert_eq!(result.to_buffer().await.unwrap(), b"avif");
+        mock.assert_async().await;
+
+        assert!(matches!(
+            source.result_accepting(&[]).await,
+            Err(crate::error::TinifyError::InvalidOptions { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_detects_expired_location() {
+        let mut server = mockito::Server::new_async().await;
+        let live = server
+            .mock("HEAD", "/output/abc")
+            .with_status(200)
+            .expect(1)
+            .create_async()
+            .await;
+        let expired = server
+            .mock("HEAD", "/output/gone")
+            .with_status(404)
+            .expect(1)
+            .create_async()
+            .await;
+        let denied = server
+            .mock("HEAD", "/output/denied")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        assert!(mock_source(&server).is_valid().await.unwrap());
+
+        let client = Arc::new(Client::new("test-key".to_string()).unwrap());
+        let gone = Source::new(format!("{}/output/gone", server.url()), Arc::clone(&client));
+        assert!(!gone.is_valid().await.unwrap());
+
+        let source = Source::new(format!("{}/output/denied", server.url()), client);
+        assert!(source.is_valid().await.is_err());
+
+        live.assert_async().await;
+        expired.assert_async().await;
+        denied.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_touch_reports_validity_without_downloading() {
+        let mut server = mockito::Server::new_async().await;
+        let head = server
+            .mock("HEAD", "/output/abc")
+            .with_status(200)
+            .expect(2)
+            .create_async()
+            .await;
+        let download = server
+            .mock("GET", "/output/abc")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        assert!(source.touch().await.unwrap());
+        // A pipeline copy refers to the same upload
+        let pipeline = source
+            .with_resize(&ResizeOptions::scale_width(100))
+            .unwrap();
+        assert!(pipeline.touch().await.unwrap());
+        head.assert_async().await;
+        download.assert_async().await;
+
+        head.remove_async().await;
+        let _expired = server
+            .mock("HEAD", "/output/abc")
+            .with_status(410)
+            .create_async()
+            .await;
+        assert!(!source.touch().await.unwrap());
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_background_ignored_by_transparent_formats() {
+        let server = mockito::Server::new();
+        let options = ConvertOptions::new(ImageFormat::Png).background("#FFFFFF");
+
+        mock_source(&server).with_convert(&options).unwrap();
+        assert!(logs_contain("Background color #FFFFFF has no effect"));
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .strict(true)
+            .build()
+            .unwrap();
+        let strict = Source::new(format!("{}/output/abc", server.url()), Arc::new(client));
+        assert!(matches!(
+            strict.with_convert(&options),
+            Err(crate::error::TinifyError::InvalidOptions { .. })
+        ));
+
+        let jpeg = ConvertOptions::new(ImageFormat::Jpeg).background("#FFFFFF");
+        assert!(strict.with_convert(&jpeg).is_ok());
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_strict_mode_rejects_empty_preserve() {
+        let server = mockito::Server::new();
+        let empty = PreserveOptions { preserve: vec![] };
+
+        mock_source(&server).with_preserve(&empty).unwrap();
+        assert!(logs_contain(
+            "Preserving an empty list of metadata has no effect"
+        ));
+
+        let client = Client::builder()
+            .api_key("test-key")
+            .strict(true)
+            .build()
+            .unwrap();
+        let strict = Source::new(format!("{}/output/abc", server.url()), Arc::new(client));
+        assert!(matches!(
+            strict.with_preserve(&empty),
+            Err(crate::error::TinifyError::InvalidOptions { .. })
+        ));
+        assert!(strict
+            .with_preserve(&PreserveOptions {
+                preserve: vec![crate::options::PreserveMetadata::Copyright],
+            })
+            .is_ok());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_transform_validates_preserve_once() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .create_async()
+            .await;
+
+        let options = TransformOptions {
+            preserve: Some(vec![]),
+            ..TransformOptions::default()
+        };
+        mock_source(&server).transform(options).await.unwrap();
+
+        logs_assert(|lines: &[&str]| {
+            match lines
+                .iter()
+                .filter(|line| line.contains("Preserving an empty list of metadata"))
+                .count()
+            {
+                1 => Ok(()),
+                n => Err(format!("expected one warning, got {}", n)),
+            }
+        });
+    }
+
+    #[test]
+    fn test_resize_dimensions_are_checked_per_method() {
+        let server = mockito::Server::new();
+        let source = mock_source(&server);
+
+        assert!(source.with_resize(&ResizeOptions::max_width(800)).is_ok());
+        assert!(source.with_resize(&ResizeOptions::max_height(600)).is_ok());
+        assert!(source
+            .with_resize(&ResizeOptions::scale_height(600))
+            .is_ok());
+
+        let both = ResizeOptions {
+            method: ResizeMethod::Scale,
+            width: Some(800),
+            height: Some(600),
+        };
+        assert!(matches!(
+            source.with_resize(&both),
+            Err(crate::error::TinifyError::InvalidOptions { message })
+                if message.contains("ResizeOptions::fit")
+        ));
+    }
+
+    #[test]
+    fn test_resize_names_missing_dimension() {
+        let server = mockito::Server::new();
+        let source = mock_source(&server);
+
+        for method in [ResizeMethod::Fit, ResizeMethod::Cover, ResizeMethod::Thumb] {
+            let cases = [
+                (
+                    Some(800),
+                    None,
+                    format!("{method:?} requires both width and height; height is missing"),
+                ),
+                (
+                    None,
+                    Some(600),
+                    format!("{method:?} requires both width and height; width is missing"),
+                ),
+                (
+                    None,
+                    None,
+                    format!(
+                        "{method:?} requires both width and height; width and height are missing"
+                    ),
+                ),
+            ];
+            for (width, height, expected) in cases {
+                let options = ResizeOptions {
+                    method: method.clone(),
+                    width,
+                    height,
+                };
+                match source.with_resize(&options) {
+                    Err(crate::error::TinifyError::InvalidOptions { message }) => {
+                        assert_eq!(message, expected)
+                    }
+                    other => panic!("expected InvalidOptions, got {other:?}"),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_processable_trait_object() {
+        let mut server = mockito::Server::new_async().await;
+        let _resize = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(
+                json!({ "resize": { "method": "scale" } }),
+            ))
+            .with_status(200)
+            .with_body("resized")
+            .create_async()
+            .await;
+        let _download = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .with_body("compressed")
+            .create_async()
+            .await;
+
+        let upload: Box<dyn Processable> = Box::new(mock_source(&server));
+
+        let mut resized = upload.resize(&ResizeOptions::max_width(100)).await.unwrap();
+        assert_eq!(resized.to_buffer().await.unwrap(), b"resized");
+        assert_eq!(upload.to_buffer().await.unwrap(), b"compressed");
+    }
+
+    #[tokio::test]
+    async fn test_dimensions_survive_store() {
+        let mut server = mockito::Server::new_async().await;
+        let _store = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::Regex("store".to_string()))
+            .with_status(200)
+            .with_header("Location", "https://bucket.s3.amazonaws.com/a.png")
+            .create_async()
+            .await;
+        let _resize = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(json!({ "resize": { "width": 320 } })))
+            .with_status(200)
+            .with_header("Image-Width", "320")
+            .with_header("Image-Height", "240")
+            .with_body("resized")
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let pipeline = source.with_resize(&ResizeOptions::max_width(320)).unwrap();
+        assert_eq!(pipeline.dimensions(), None);
+
+        pipeline.to_buffer().await.unwrap();
+        let stored = pipeline
+            .store(StoreOptions::S3(crate::options::S3Options::new(
+                "key".to_string(),
+                "secret".to_string(),
+                "us-east-1".to_string(),
+                "bucket/a.png".to_string(),
+            )))
+            .await
+            .unwrap();
+
+        assert_eq!(stored.image_width(), None);
+        assert_eq!(pipeline.dimensions(), Some((320, 240)));
+        assert_eq!(source.dimensions(), None);
+    }
+
+    #[tokio::test]
+    async fn test_dimensions_are_kept_per_operation() {
+        let mut server = mockito::Server::new_async().await;
+        let _small = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(json!({ "resize": { "width": 100 } })))
+            .with_status(200)
+            .with_header("Image-Width", "100")
+            .with_header("Image-Height", "75")
+            .create_async()
+            .await;
+        let _large = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(json!({ "resize": { "width": 800 } })))
+            .with_status(200)
+            .with_header("Image-Width", "800")
+            .with_header("Image-Height", "600")
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let small = source.with_resize(&ResizeOptions::max_width(100)).unwrap();
+        let large = source.with_resize(&ResizeOptions::max_width(800)).unwrap();
+
+        let (small_result, large_result) = tokio::join!(small.to_buffer(), large.to_buffer());
+        small_result.unwrap();
+        large_result.unwrap();
+
+        assert_eq!(small.dimensions(), Some((100, 75)));
+        assert_eq!(large.dimensions(), Some((800, 600)));
+        assert_eq!(source.dimensions(), None);
+    }
+
+    #[tokio::test]
+    async fn test_counted_as_compression() {
+        let mut server = mockito::Server::new_async().await;
+        let _post = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .create_async()
+            .await;
+        let _get = server
+            .mock("GET", "/output/abc")
+            .with_status(200)
+            .create_async()
+            .await;
+        let source = mock_source(&server);
+
+        let resized = source.resize(ResizeOptions::max_width(100)).await.unwrap();
+        assert!(resized.counted_as_compression());
+
+        let converted = source
+            .with_preserve(&PreserveOptions {
+                preserve: vec![PreserveMetadata::Copyright],
+            })
+            .unwrap()
+            .convert(ConvertOptions::new(ImageFormat::WebP))
+            .await
+            .unwrap();
+        assert!(converted.counted_as_compression());
+
+        let preserved = source
+            .preserve(PreserveOptions {
+                preserve: vec![PreserveMetadata::Copyright],
+            })
+            .await
+            .unwrap();
+        assert!(!preserved.counted_as_compression());
+
+        let stored = source
+            .store(StoreOptions::Custom(
+                json!({ "service": "s3", "path": "a.png" }),
+            ))
+            .await
+            .unwrap();
+        assert!(!stored.counted_as_compression());
+
+        assert!(!source.result().await.unwrap().counted_as_compression());
+    }
+
+    #[tokio::test]
+    async fn test_store_many_collects_per_destination_results() {
+        let mut server = mockito::Server::new_async().await;
+        let _ok = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::Regex(r#""path":"good/a.png""#.to_string()))
+            .with_status(200)
+            .with_header("Location", "https://good.s3.amazonaws.com/a.png")
+            .create_async()
+            .await;
+        let _denied = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::Regex(r#""path":"denied/a.png""#.to_string()))
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Access denied"}"#)
+            .create_async()
+            .await;
+
+        let destination = |path: &str| {
+            StoreOptions::S3(crate::options::S3Options::new(
+                "key".to_string(),
+                "secret".to_string(),
+                "us-east-1".to_string(),
+                path.to_string(),
+            ))
+        };
+
+        let results = mock_source(&server)
+            .store_many(vec![destination("good/a.png"), destination("denied/a.png")])
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().unwrap().header("Location"),
+            Some("https://good.s3.amazonaws.com/a.png")
+        );
+        assert!(matches!(
+            &results[1],
+            Err(crate::error::TinifyError::ClientError { message, .. }) if message == "Access denied"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_store_result_keeps_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(
+                json!({ "store": { "service": "s3" } }),
+            ))
+            .with_status(200)
+            .with_header("Location", "https://bucket.s3.amazonaws.com/a.png")
+            .with_header("Compression-Count", "42")
+            .create_async()
+            .await;
+
+        let result = mock_source(&server)
+            .store(StoreOptions::S3(crate::options::S3Options::new(
+                "key".to_string(),
+                "secret".to_string(),
+                "us-east-1".to_string(),
+                "bucket/a.png".to_string(),
+            )))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.location(),
+            Some("https://bucket.s3.amazonaws.com/a.png")
+        );
+        assert_eq!(result.compression_count(), Some(42));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_store_headers_are_rejected() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let mut options = crate::options::S3Options::new(
+            "key".to_string(),
+            "secret".to_string(),
+            "us-east-1".to_string(),
+            "bucket/a.png".to_string(),
+        );
+        options.headers = Some(json!({ "Cache-Control": "x".repeat(100 * 1024) }).into());
+
+        let result = mock_source(&server).store(StoreOptions::S3(options)).await;
+        assert!(matches!(
+            result,
+            Err(crate::error::TinifyError::InvalidOptions { message })
+                if message.contains("above the limit of 65536 bytes")
+        ));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_sends_chained_operations_once() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/output/abc")
+            .match_header("Content-Type", "application/json")
+            .match_body(Matcher::Json(json!({
+                "resize": { "method": "fit", "width": 300, "height": 200 },
+                "convert": { "type": "image/webp" }
+            })))
+            .with_status(200)
+            .with_header("Content-Type", "image/webp")
+            .with_body("webp-bytes")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let pipeline = source
+            .with_resize(&ResizeOptions::fit(300, 200))
+            .unwrap()
+            .with_convert(&ConvertOptions::new(ImageFormat::WebP))
+            .unwrap();
+
+        // Building the pipeline is local; the original source is left untouched
+        assert!(source.commands.is_empty());
+        assert!(source.with_resize(&ResizeOptions::fit(0, 200)).is_err());
+
+        assert_eq!(pipeline.to_buffer().await.unwrap(), b"webp-bytes");
+        mock.assert_async().await;
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_write_responsive_set_collects_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let small = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(json!({ "resize": { "width": 320 } })))
+            .with_status(200)
+            .with_header("Image-Width", "320")
+            .with_header("Image-Height", "240")
+            .with_body("small")
+            .create_async()
+            .await;
+        let large = server
+            .mock("POST", "/output/abc")
+            .match_body(Matcher::PartialJson(json!({ "resize": { "width": 640 } })))
+            .with_status(400)
+            .with_body(r#"{"error":"BadRequest","message":"Resize failed"}"#)
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let dir = tempfile::tempdir().unwrap();
+
+        let set = source
+            .write_responsive_set(
+                &[
+                    ResizeOptions::scale_width(320),
+                    ResizeOptions::scale_width(640),
+                ],
+                dir.path(),
+                "photo",
+                "png",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(set.files, vec![dir.path().join("photo-320x240.png")]);
+        assert_eq!(set.srcset, "photo-320x240.png 320w");
+        assert_eq!(set.errors.len(), 1);
+        assert_eq!(set.errors[0].0.width, Some(640));
+        assert_eq!(std::fs::read(&set.files[0]).unwrap(), b"small");
+        small.assert_async().await;
+        large.assert_async().await;
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_write_responsive_set_skips_unknown_dimensions() {
+        let mut server = mockito::Server::new_async().await;
+        let _unsized = server
+            .mock("POST", "/output/abc")
+            .with_status(200)
+            .with_body("resized")
+            .create_async()
+            .await;
+
+        let source = mock_source(&server);
+        let dir = tempfile::tempdir().unwrap();
+
+        let set = source
+            .write_responsive_set(
+                &[ResizeOptions::scale_width(320)],
+                dir.path(),
+                "photo",
+                "png",
+            )
+            .await
+            .unwrap();
+
+        assert!(set.files.is_empty());
+        assert_eq!(set.srcset, "");
+        assert!(matches!(
+            set.errors[..],
+            [(_, crate::error::TinifyError::UnknownError { .. })]
+        ));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
 }